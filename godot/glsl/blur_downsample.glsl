@@ -32,7 +32,8 @@ void main() {
 
 layout(push_constant, std430) uniform Blur {
     vec2 source_pixel_size; // 08 - 8
-    vec2 pad1; // 08 - 16
+    float firefly_suppression; // 04 - 12
+    float glow_knee; // 04 - 16
 
     // Glow.
     float glow_strength; // 04 - 20
@@ -43,7 +44,7 @@ layout(push_constant, std430) uniform Blur {
     float glow_exposure; // 04 - 36
     float glow_luminance_cap; // 04 - 40
     float luminance_multiplier; // 04 - 44
-    float pad2; // 04 - 48
+    float glow_threshold; // 04 - 48
 }
 blur;
 
@@ -73,7 +74,17 @@ vec4 BloomDownKernel4(sampler2D Tex, vec2 uv0) {
 	return c;
 }
 
+// Keep in sync with LUMINANCE_WEIGHTS in karis.rs.
+const vec3 KARIS_LUMINANCE_WEIGHTS = vec3(0.2126, 0.7152, 0.0722);
+
+// Karis average weight, see karis.rs. Fades from a plain average at 0 suppression.
+float karis_weight(vec3 color) {
+	float luma = dot(color * blur.luminance_multiplier * blur.glow_exposure, KARIS_LUMINANCE_WEIGHTS);
+	return 1.0 / (1.0 + max(luma, 0.0) * blur.firefly_suppression);
+}
+
 layout(constant_id = 0) const bool first_pass = false;
+layout(constant_id = 1) const bool soft_knee = false;
 
 void main() {
 	// We do not apply our color scale for our mobile renderer here, we'll leave our colors at half brightness and apply scale in the tonemap raster.
@@ -87,11 +98,17 @@ void main() {
 		block_pos = clamp(block_pos, vec2(0.0), end);
 
 		// We skipped a level, so gather 16 closest samples now.
-		vec4 color = textureLod(source_color, (block_pos + vec2(0.5, 0.5)) * blur.source_pixel_size, 0.0);
-		color += textureLod(source_color, (block_pos + vec2(0.5, 2.5)) * blur.source_pixel_size, 0.0);
-		color += textureLod(source_color, (block_pos + vec2(2.5, 0.5)) * blur.source_pixel_size, 0.0);
-		color += textureLod(source_color, (block_pos + vec2(2.5, 2.5)) * blur.source_pixel_size, 0.0);
-		frag_color = color * 0.25;
+		vec4 c0 = textureLod(source_color, (block_pos + vec2(0.5, 0.5)) * blur.source_pixel_size, 0.0);
+		vec4 c1 = textureLod(source_color, (block_pos + vec2(0.5, 2.5)) * blur.source_pixel_size, 0.0);
+		vec4 c2 = textureLod(source_color, (block_pos + vec2(2.5, 0.5)) * blur.source_pixel_size, 0.0);
+		vec4 c3 = textureLod(source_color, (block_pos + vec2(2.5, 2.5)) * blur.source_pixel_size, 0.0);
+		if (blur.firefly_suppression > 0.0) {
+			// Luma weighted average, keeps single very bright pixels from flickering in the glow.
+			vec4 w = vec4(karis_weight(c0.rgb), karis_weight(c1.rgb), karis_weight(c2.rgb), karis_weight(c3.rgb));
+			frag_color = (c0 * w.x + c1 * w.y + c2 * w.z + c3 * w.w) / dot(w, vec4(1.0));
+		} else {
+			frag_color = (c0 + c1 + c2 + c3) * 0.25;
+		}
 
 		// Apply strength a second time since it usually gets added at each level.
 		frag_color *= blur.glow_strength;
@@ -103,7 +120,17 @@ void main() {
 		frag_color *= blur.glow_exposure;
 
 		float luminance = max(frag_color.r, max(frag_color.g, frag_color.b));
-		float feedback = max(smoothstep(blur.glow_hdr_threshold, blur.glow_hdr_threshold + blur.glow_hdr_scale, luminance), blur.glow_bloom);
+		float feedback;
+		if (soft_knee) {
+			// Quadratic knee around the threshold, linear above it.
+			float knee = blur.glow_knee;
+			float rq = clamp(luminance - blur.glow_threshold + knee, 0.0, 2.0 * knee);
+			rq = rq * rq / (4.0 * knee + 1e-5);
+			feedback = max(rq, luminance - blur.glow_threshold) / max(luminance, 1e-5);
+		} else {
+			feedback = smoothstep(blur.glow_hdr_threshold, blur.glow_hdr_threshold + blur.glow_hdr_scale, luminance);
+		}
+		feedback = max(feedback, blur.glow_bloom);
 
 		frag_color = min(frag_color * feedback, vec4(blur.glow_luminance_cap)) / blur.luminance_multiplier;
 	} else {
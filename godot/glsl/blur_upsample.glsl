@@ -38,6 +38,11 @@ layout(push_constant, std430) uniform Blur {
     // Glow.
     float glow_strength; // 04 - 24
     vec2 pad; // 08 - 32
+
+    vec3 glow_level_tint; // 12 - 44
+    float pad2; // 04 - 48
+    vec3 glow_strength_tint; // 12 - 60
+    float pad3; // 04 - 64
 }
 blur;
 
@@ -156,8 +161,10 @@ void main() {
 	// We do not apply our color scale for our mobile renderer here, we'll leave our colors at half brightness and apply scale in the tonemap raster.
 
 	frag_color = BloomUpKernel4(source_color, floor(gl_FragCoord.xy)) * blur.glow_strength; // "glow_strength" here is actually the glow level. It is always 1.0, except for the first upsample where we need to apply the level to two textures at once.
+	frag_color.rgb *= blur.glow_strength_tint;
 	if (use_blend_color) {
 		vec2 uv = floor(gl_FragCoord.xy) + 0.5;
-		frag_color += textureLod(blend_color, uv * blur.dest_pixel_size, 0.0) * blur.glow_level;
+		vec4 blend = textureLod(blend_color, uv * blur.dest_pixel_size, 0.0) * blur.glow_level;
+		frag_color += vec4(blend.rgb * blur.glow_level_tint, blend.a);
 	}
 }
@@ -0,0 +1,70 @@
+/* clang-format off */
+#[vertex]
+
+#version 450
+
+layout(location = 0) out vec2 uv_interp;
+/* clang-format on */
+
+void main() {
+	vec2 vertex_base;
+	if (gl_VertexIndex == 0) {
+		vertex_base = vec2(-1.0, -1.0);
+	} else if (gl_VertexIndex == 1) {
+		vertex_base = vec2(-1.0, 3.0);
+	} else {
+		vertex_base = vec2(3.0, -1.0);
+	}
+	gl_Position = vec4(vertex_base, 0.0, 1.0);
+	uv_interp = clamp(vertex_base, vec2(0.0, 0.0), vec2(1.0, 1.0)) * 2.0; // saturate(x) * 2.0
+}
+
+/* clang-format off */
+#[fragment]
+
+#version 450
+
+layout(push_constant, std430) uniform Cas {
+    // -1 / mix(8, 5, sharpness).
+    float peak; // 04 - 04
+    float pad0; // 04 - 08
+    vec2 pad1; // 08 - 16
+}
+cas;
+
+layout(location = 0) in vec2 uv_interp;
+/* clang-format on */
+
+layout(set = 0, binding = 0) uniform sampler2D source_color;
+
+layout(location = 0) out vec4 frag_color;
+
+// AMD FidelityFX Contrast Adaptive Sharpening, without the upscaling path.
+// https://github.com/GPUOpen-Effects/FidelityFX-CAS
+void main() {
+	// a b c
+	// d e f
+	// g h i
+	vec3 a = textureLodOffset(source_color, uv_interp, 0.0, ivec2(-1, -1)).rgb;
+	vec3 b = textureLodOffset(source_color, uv_interp, 0.0, ivec2(0, -1)).rgb;
+	vec3 c = textureLodOffset(source_color, uv_interp, 0.0, ivec2(1, -1)).rgb;
+	vec3 d = textureLodOffset(source_color, uv_interp, 0.0, ivec2(-1, 0)).rgb;
+	vec4 e = textureLod(source_color, uv_interp, 0.0);
+	vec3 f = textureLodOffset(source_color, uv_interp, 0.0, ivec2(1, 0)).rgb;
+	vec3 g = textureLodOffset(source_color, uv_interp, 0.0, ivec2(-1, 1)).rgb;
+	vec3 h = textureLodOffset(source_color, uv_interp, 0.0, ivec2(0, 1)).rgb;
+	vec3 i = textureLodOffset(source_color, uv_interp, 0.0, ivec2(1, 1)).rgb;
+
+	// Soft min and max, the cross plus the whole 3x3 neighborhood.
+	vec3 mn = min(min(min(d, e.rgb), min(f, b)), h);
+	mn += min(mn, min(min(a, c), min(g, i)));
+	vec3 mx = max(max(max(d, e.rgb), max(f, b)), h);
+	mx += max(mx, max(max(a, c), max(g, i)));
+
+	// Sharpen less where the local contrast is already high.
+	vec3 amp = clamp(min(mn, 2.0 - mx) / max(mx, vec3(1e-5)), vec3(0.0), vec3(1.0));
+	vec3 w = sqrt(amp) * cas.peak;
+
+	vec3 color = ((b + d + f + h) * w + e.rgb) / (1.0 + 4.0 * w);
+	frag_color = vec4(max(color, vec3(0.0)), e.a);
+}
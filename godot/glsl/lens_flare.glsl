@@ -0,0 +1,98 @@
+/* clang-format off */
+#[vertex]
+
+#version 450
+
+layout(location = 0) out vec2 uv_interp;
+/* clang-format on */
+
+void main() {
+	vec2 vertex_base;
+	if (gl_VertexIndex == 0) {
+		vertex_base = vec2(-1.0, -1.0);
+	} else if (gl_VertexIndex == 1) {
+		vertex_base = vec2(-1.0, 3.0);
+	} else {
+		vertex_base = vec2(3.0, -1.0);
+	}
+	gl_Position = vec4(vertex_base, 0.0, 1.0);
+	uv_interp = clamp(vertex_base, vec2(0.0, 0.0), vec2(1.0, 1.0)) * 2.0; // saturate(x) * 2.0
+}
+
+/* clang-format off */
+#[fragment]
+
+#version 450
+
+layout(push_constant, std430) uniform LensFlare {
+    vec2 source_pixel_size; // 08 - 08
+    float ghost_dispersal; // 04 - 12
+    float halo_width; // 04 - 16
+
+    float ghost_intensity; // 04 - 20
+    float halo_intensity; // 04 - 24
+    // In source pixels.
+    float chromatic_distortion; // 04 - 28
+    float starburst_intensity; // 04 - 32
+
+    float starburst_rotation; // 04 - 36
+    // Width over height.
+    float aspect; // 04 - 40
+    int ghost_count; // 04 - 44
+    float pad; // 04 - 48
+}
+flare;
+
+layout(location = 0) in vec2 uv_interp;
+/* clang-format on */
+
+layout(set = 0, binding = 0) uniform sampler2D source_color;
+layout(set = 0, binding = 1) uniform sampler2D ghost_gradient;
+
+layout(location = 0) out vec4 frag_color;
+
+// Distance from the center to a corner in uv space.
+#define MAX_CENTER_DISTANCE 0.70710678
+
+vec3 sample_chromatic(vec2 uv, vec2 direction) {
+	vec2 offset = direction * flare.chromatic_distortion * flare.source_pixel_size;
+	return vec3(
+			textureLod(source_color, uv + offset, 0.0).r,
+			textureLod(source_color, uv, 0.0).g,
+			textureLod(source_color, uv - offset, 0.0).b);
+}
+
+float center_weight(vec2 uv, float falloff) {
+	return pow(1.0 - clamp(length(vec2(0.5) - uv) / MAX_CENTER_DISTANCE, 0.0, 1.0), falloff);
+}
+
+// Pseudo lens flare after John Chapman, ghosts and the halo are reflections of the
+// bright pass through the center of the screen.
+void main() {
+	vec2 uv = vec2(1.0) - uv_interp;
+	vec2 ghost_vec = (vec2(0.5) - uv) * flare.ghost_dispersal;
+	vec2 aspect_scale = vec2(flare.aspect, 1.0);
+	vec2 to_center = (vec2(0.5) - uv) * aspect_scale;
+	vec2 direction = length(to_center) > 1e-5 ? normalize(to_center) : vec2(0.0);
+
+	vec3 ghosts = vec3(0.0);
+	for (int i = 0; i < flare.ghost_count; i++) {
+		vec2 offset = fract(uv + ghost_vec * float(i));
+		ghosts += sample_chromatic(offset, direction) * center_weight(offset, 10.0);
+	}
+	// Color the ghosts by their distance from the center.
+	vec3 ghost_color = textureLod(ghost_gradient, vec2(length(vec2(0.5) - uv) / MAX_CENTER_DISTANCE, 0.5), 0.0).rgb;
+	vec3 result = ghosts * ghost_color * flare.ghost_intensity;
+
+	// The halo is a ring of constant radius, corrected for the aspect ratio.
+	vec2 halo_uv = fract(uv + direction / aspect_scale * flare.halo_width);
+	result += sample_chromatic(halo_uv, direction) * center_weight(halo_uv, 5.0) * flare.halo_intensity;
+
+	// Starburst, rotating with the camera so it doesn't look glued to the screen.
+	vec2 centered = (uv_interp - vec2(0.5)) * aspect_scale;
+	float angle = atan(centered.y, centered.x) + flare.starburst_rotation;
+	float starburst = 0.5 + 0.5 * cos(angle * 12.0) * cos(angle * 5.0 + 0.7);
+	result *= mix(1.0, starburst * 2.0, flare.starburst_intensity);
+
+	frag_color = vec4(result, 1.0);
+}
@@ -0,0 +1,50 @@
+#[compute]
+
+#version 450
+
+// Keep in sync with HISTOGRAM_BINS in auto_exposure.rs.
+#define HISTOGRAM_BINS 64
+
+layout(local_size_x = 16, local_size_y = 16, local_size_z = 1) in;
+
+layout(set = 0, binding = 0) uniform sampler2D source_color;
+
+layout(set = 0, binding = 1, std430) restrict buffer Histogram {
+	uint bins[HISTOGRAM_BINS];
+}
+histogram;
+
+layout(push_constant, std430) uniform Params {
+	ivec2 source_size; // 08 - 08
+	float min_ev; // 04 - 12
+	float inv_ev_range; // 04 - 16
+}
+params;
+
+shared uint local_bins[HISTOGRAM_BINS];
+
+void main() {
+	uint index = gl_LocalInvocationIndex;
+	if (index < HISTOGRAM_BINS) {
+		local_bins[index] = 0;
+	}
+	barrier();
+
+	ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+	if (all(lessThan(pos, params.source_size))) {
+		vec3 color = texelFetch(source_color, pos, 0).rgb;
+		float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+		// Bin 0 collects black pixels, the rest cover [min_ev, max_ev].
+		uint bin = 0;
+		if (luminance > 1e-6) {
+			float t = clamp((log2(luminance) - params.min_ev) * params.inv_ev_range, 0.0, 1.0);
+			bin = uint(t * float(HISTOGRAM_BINS - 2) + 1.0);
+		}
+		atomicAdd(local_bins[bin], 1);
+	}
+	barrier();
+
+	if (index < HISTOGRAM_BINS) {
+		atomicAdd(histogram.bins[index], local_bins[index]);
+	}
+}
@@ -0,0 +1,194 @@
+/* clang-format off */
+#[vertex]
+
+#version 450
+
+layout(location = 0) out vec2 uv_interp;
+/* clang-format on */
+
+void main() {
+	vec2 vertex_base;
+	if (gl_VertexIndex == 0) {
+		vertex_base = vec2(-1.0, -1.0);
+	} else if (gl_VertexIndex == 1) {
+		vertex_base = vec2(-1.0, 3.0);
+	} else {
+		vertex_base = vec2(3.0, -1.0);
+	}
+	gl_Position = vec4(vertex_base, 0.0, 1.0);
+	uv_interp = clamp(vertex_base, vec2(0.0, 0.0), vec2(1.0, 1.0)) * 2.0; // saturate(x) * 2.0
+}
+
+/* clang-format off */
+#[fragment]
+
+#version 450
+
+layout(push_constant, std430) uniform Smaa {
+    // (1 / width, 1 / height, width, height).
+    vec4 rt_metrics; // 16 - 16
+    float threshold; // 04 - 20
+    float max_search_steps; // 04 - 24
+    // 1.0 disables corner detection.
+    float corner_rounding_norm; // 04 - 28
+    float pad; // 04 - 32
+}
+smaa;
+
+layout(location = 0) in vec2 uv_interp;
+/* clang-format on */
+
+layout(set = 0, binding = 0) uniform sampler2D edges_tex;
+layout(set = 0, binding = 1) uniform sampler2D area_tex;
+// Point sampled.
+layout(set = 0, binding = 2) uniform sampler2D search_tex;
+
+layout(location = 0) out vec4 frag_color;
+
+// SMAA 1x blending weight calculation, https://github.com/iryoku/smaa.
+// Orthogonal patterns only, the area texture is generated without the diagonal half.
+
+#define SMAA_AREATEX_MAX_DISTANCE 16.0
+#define SMAA_AREATEX_PIXEL_SIZE (1.0 / vec2(80.0, 80.0))
+#define SMAA_SEARCHTEX_SIZE vec2(66.0, 33.0)
+#define SMAA_SEARCHTEX_PACKED_SIZE vec2(64.0, 16.0)
+
+float search_length(vec2 e, float offset) {
+	// The search texture is cropped and flipped vertically, see smaa_tables.rs.
+	vec2 scale = SMAA_SEARCHTEX_SIZE * vec2(0.5, -1.0);
+	vec2 bias = SMAA_SEARCHTEX_SIZE * vec2(offset, 1.0);
+	scale += vec2(-1.0, 1.0);
+	bias += vec2(0.5, -0.5);
+	scale /= SMAA_SEARCHTEX_PACKED_SIZE;
+	bias /= SMAA_SEARCHTEX_PACKED_SIZE;
+	return textureLod(search_tex, fma(scale, e, bias), 0.0).r;
+}
+
+float search_x_left(vec2 uv, float end) {
+	// Bilinear fetches between two edges tell if the search can continue 2 pixels at a time.
+	vec2 e = vec2(0.0, 1.0);
+	while (uv.x > end && e.g > 0.8281 && e.r == 0.0) {
+		e = textureLod(edges_tex, uv, 0.0).rg;
+		uv = fma(-vec2(2.0, 0.0), smaa.rt_metrics.xy, uv);
+	}
+	float offset = fma(-(255.0 / 127.0), search_length(e, 0.0), 3.25);
+	return fma(smaa.rt_metrics.x, offset, uv.x);
+}
+
+float search_x_right(vec2 uv, float end) {
+	vec2 e = vec2(0.0, 1.0);
+	while (uv.x < end && e.g > 0.8281 && e.r == 0.0) {
+		e = textureLod(edges_tex, uv, 0.0).rg;
+		uv = fma(vec2(2.0, 0.0), smaa.rt_metrics.xy, uv);
+	}
+	float offset = fma(-(255.0 / 127.0), search_length(e, 0.5), 3.25);
+	return fma(-smaa.rt_metrics.x, offset, uv.x);
+}
+
+float search_y_up(vec2 uv, float end) {
+	vec2 e = vec2(1.0, 0.0);
+	while (uv.y > end && e.r > 0.8281 && e.g == 0.0) {
+		e = textureLod(edges_tex, uv, 0.0).rg;
+		uv = fma(-vec2(0.0, 2.0), smaa.rt_metrics.xy, uv);
+	}
+	float offset = fma(-(255.0 / 127.0), search_length(e.gr, 0.0), 3.25);
+	return fma(smaa.rt_metrics.y, offset, uv.y);
+}
+
+float search_y_down(vec2 uv, float end) {
+	vec2 e = vec2(1.0, 0.0);
+	while (uv.y < end && e.r > 0.8281 && e.g == 0.0) {
+		e = textureLod(edges_tex, uv, 0.0).rg;
+		uv = fma(vec2(0.0, 2.0), smaa.rt_metrics.xy, uv);
+	}
+	float offset = fma(-(255.0 / 127.0), search_length(e.gr, 0.5), 3.25);
+	return fma(-smaa.rt_metrics.y, offset, uv.y);
+}
+
+vec2 area(vec2 dist, float e1, float e2) {
+	// Rounding prevents precision errors of bilinear filtering.
+	vec2 uv = fma(vec2(SMAA_AREATEX_MAX_DISTANCE), round(4.0 * vec2(e1, e2)), dist);
+	uv = fma(SMAA_AREATEX_PIXEL_SIZE, uv, 0.5 * SMAA_AREATEX_PIXEL_SIZE);
+	return textureLod(area_tex, uv, 0.0).rg;
+}
+
+void detect_horizontal_corner_pattern(inout vec2 weights, vec4 uv, vec2 d) {
+	vec2 left_right = step(d.xy, d.yx);
+	vec2 rounding = (1.0 - smaa.corner_rounding_norm) * left_right;
+	// Reduce blending for pixels in the center of a line.
+	rounding /= left_right.x + left_right.y;
+
+	vec2 factor = vec2(1.0);
+	factor.x -= rounding.x * textureLodOffset(edges_tex, uv.xy, 0.0, ivec2(0, 1)).r;
+	factor.x -= rounding.y * textureLodOffset(edges_tex, uv.zw, 0.0, ivec2(1, 1)).r;
+	factor.y -= rounding.x * textureLodOffset(edges_tex, uv.xy, 0.0, ivec2(0, -2)).r;
+	factor.y -= rounding.y * textureLodOffset(edges_tex, uv.zw, 0.0, ivec2(1, -2)).r;
+	weights *= clamp(factor, vec2(0.0), vec2(1.0));
+}
+
+void detect_vertical_corner_pattern(inout vec2 weights, vec4 uv, vec2 d) {
+	vec2 left_right = step(d.xy, d.yx);
+	vec2 rounding = (1.0 - smaa.corner_rounding_norm) * left_right;
+	rounding /= left_right.x + left_right.y;
+
+	vec2 factor = vec2(1.0);
+	factor.x -= rounding.x * textureLodOffset(edges_tex, uv.xy, 0.0, ivec2(1, 0)).g;
+	factor.x -= rounding.y * textureLodOffset(edges_tex, uv.zw, 0.0, ivec2(1, 1)).g;
+	factor.y -= rounding.x * textureLodOffset(edges_tex, uv.xy, 0.0, ivec2(-2, 0)).g;
+	factor.y -= rounding.y * textureLodOffset(edges_tex, uv.zw, 0.0, ivec2(-2, 1)).g;
+	weights *= clamp(factor, vec2(0.0), vec2(1.0));
+}
+
+void main() {
+	vec2 pixcoord = uv_interp * smaa.rt_metrics.zw;
+	vec4 offset0 = fma(smaa.rt_metrics.xyxy, vec4(-0.25, -0.125, 1.25, -0.125), uv_interp.xyxy);
+	vec4 offset1 = fma(smaa.rt_metrics.xyxy, vec4(-0.125, -0.25, -0.125, 1.25), uv_interp.xyxy);
+	// Search range ends.
+	vec4 offset2 = fma(smaa.rt_metrics.xxyy, vec4(-2.0, 2.0, -2.0, 2.0) * smaa.max_search_steps, vec4(offset0.xz, offset1.yw));
+
+	vec4 weights = vec4(0.0);
+	vec2 e = textureLod(edges_tex, uv_interp, 0.0).rg;
+
+	if (e.g > 0.0) {
+		// Edge at north.
+		vec2 d;
+		vec3 coords;
+		coords.x = search_x_left(offset0.xy, offset2.x);
+		coords.y = offset1.y;
+		d.x = coords.x;
+		// Fetching the crossing edges of both ends in one go.
+		float e1 = textureLod(edges_tex, coords.xy, 0.0).r;
+		coords.z = search_x_right(offset0.zw, offset2.y);
+		d.y = coords.z;
+
+		d = abs(round(fma(smaa.rt_metrics.zz, d, -pixcoord.xx)));
+		vec2 sqrt_d = sqrt(d);
+		float e2 = textureLodOffset(edges_tex, coords.zy, 0.0, ivec2(1, 0)).r;
+		weights.rg = area(sqrt_d, e1, e2);
+
+		coords.y = uv_interp.y;
+		detect_horizontal_corner_pattern(weights.rg, coords.xyzy, d);
+	}
+
+	if (e.r > 0.0) {
+		// Edge at west.
+		vec2 d;
+		vec3 coords;
+		coords.y = search_y_up(offset1.xy, offset2.z);
+		coords.x = offset0.x;
+		d.x = coords.y;
+		float e1 = textureLod(edges_tex, coords.xy, 0.0).g;
+		coords.z = search_y_down(offset1.zw, offset2.w);
+		d.y = coords.z;
+
+		d = abs(round(fma(smaa.rt_metrics.ww, d, -pixcoord.yy)));
+		vec2 sqrt_d = sqrt(d);
+		float e2 = textureLodOffset(edges_tex, coords.xz, 0.0, ivec2(0, 1)).g;
+		weights.ba = area(sqrt_d, e1, e2);
+
+		coords.x = uv_interp.x;
+		detect_vertical_corner_pattern(weights.ba, coords.xyxz, d);
+	}
+
+	frag_color = weights;
+}
@@ -0,0 +1,89 @@
+/* clang-format off */
+#[vertex]
+
+#version 450
+
+layout(location = 0) out vec2 uv_interp;
+/* clang-format on */
+
+void main() {
+	vec2 vertex_base;
+	if (gl_VertexIndex == 0) {
+		vertex_base = vec2(-1.0, -1.0);
+	} else if (gl_VertexIndex == 1) {
+		vertex_base = vec2(-1.0, 3.0);
+	} else {
+		vertex_base = vec2(3.0, -1.0);
+	}
+	gl_Position = vec4(vertex_base, 0.0, 1.0);
+	uv_interp = clamp(vertex_base, vec2(0.0, 0.0), vec2(1.0, 1.0)) * 2.0; // saturate(x) * 2.0
+}
+
+/* clang-format off */
+#[fragment]
+
+#version 450
+
+layout(push_constant, std430) uniform Smaa {
+    // (1 / width, 1 / height, width, height).
+    vec4 rt_metrics; // 16 - 16
+    float threshold; // 04 - 20
+    float max_search_steps; // 04 - 24
+    // 1.0 disables corner detection.
+    float corner_rounding_norm; // 04 - 28
+    float pad; // 04 - 32
+}
+smaa;
+
+layout(location = 0) in vec2 uv_interp;
+/* clang-format on */
+
+layout(set = 0, binding = 0) uniform sampler2D source_color;
+
+layout(location = 0) out vec4 frag_color;
+
+// SMAA 1x luma edge detection, https://github.com/iryoku/smaa.
+// Edges are written to every pixel instead of discarding, so the target needs no clear.
+
+#define SMAA_LOCAL_CONTRAST_ADAPTATION_FACTOR 2.0
+
+float luma(vec2 uv) {
+	// The thresholds are tuned for gamma-corrected colors.
+	vec3 color = clamp(textureLod(source_color, uv, 0.0).rgb, vec3(0.0), vec3(1.0));
+	const vec3 a = vec3(0.055f);
+	color = mix((vec3(1.0f) + a) * pow(color, vec3(1.0f / 2.4f)) - a, 12.92f * color, lessThan(color, vec3(0.0031308f)));
+	return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+void main() {
+	vec4 offset0 = fma(smaa.rt_metrics.xyxy, vec4(-1.0, 0.0, 0.0, -1.0), uv_interp.xyxy);
+	vec4 offset1 = fma(smaa.rt_metrics.xyxy, vec4(1.0, 0.0, 0.0, 1.0), uv_interp.xyxy);
+	vec4 offset2 = fma(smaa.rt_metrics.xyxy, vec4(-2.0, 0.0, 0.0, -2.0), uv_interp.xyxy);
+
+	float l = luma(uv_interp);
+	float l_left = luma(offset0.xy);
+	float l_top = luma(offset0.zw);
+
+	vec4 delta;
+	delta.xy = abs(l - vec2(l_left, l_top));
+	vec2 edges = step(vec2(smaa.threshold), delta.xy);
+	if (dot(edges, vec2(1.0)) == 0.0) {
+		frag_color = vec4(0.0);
+		return;
+	}
+
+	float l_right = luma(offset1.xy);
+	float l_bottom = luma(offset1.zw);
+	delta.zw = abs(l - vec2(l_right, l_bottom));
+	vec2 max_delta = max(delta.xy, delta.zw);
+
+	float l_left_left = luma(offset2.xy);
+	float l_top_top = luma(offset2.zw);
+	delta.zw = abs(vec2(l_left, l_top) - vec2(l_left_left, l_top_top));
+	max_delta = max(max_delta.xy, delta.zw);
+	float final_delta = max(max_delta.x, max_delta.y);
+
+	// Local contrast adaptation.
+	edges *= step(final_delta, SMAA_LOCAL_CONTRAST_ADAPTATION_FACTOR * delta.xy);
+	frag_color = vec4(edges, 0.0, 0.0);
+}
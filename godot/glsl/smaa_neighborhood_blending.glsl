@@ -0,0 +1,70 @@
+/* clang-format off */
+#[vertex]
+
+#version 450
+
+layout(location = 0) out vec2 uv_interp;
+/* clang-format on */
+
+void main() {
+	vec2 vertex_base;
+	if (gl_VertexIndex == 0) {
+		vertex_base = vec2(-1.0, -1.0);
+	} else if (gl_VertexIndex == 1) {
+		vertex_base = vec2(-1.0, 3.0);
+	} else {
+		vertex_base = vec2(3.0, -1.0);
+	}
+	gl_Position = vec4(vertex_base, 0.0, 1.0);
+	uv_interp = clamp(vertex_base, vec2(0.0, 0.0), vec2(1.0, 1.0)) * 2.0; // saturate(x) * 2.0
+}
+
+/* clang-format off */
+#[fragment]
+
+#version 450
+
+layout(push_constant, std430) uniform Smaa {
+    // (1 / width, 1 / height, width, height).
+    vec4 rt_metrics; // 16 - 16
+    float threshold; // 04 - 20
+    float max_search_steps; // 04 - 24
+    // 1.0 disables corner detection.
+    float corner_rounding_norm; // 04 - 28
+    float pad; // 04 - 32
+}
+smaa;
+
+layout(location = 0) in vec2 uv_interp;
+/* clang-format on */
+
+layout(set = 0, binding = 0) uniform sampler2D source_color;
+layout(set = 0, binding = 1) uniform sampler2D blend_tex;
+
+layout(location = 0) out vec4 frag_color;
+
+// SMAA 1x neighborhood blending, https://github.com/iryoku/smaa.
+// The source is linear, so the bilinear fetches blend in linear space.
+void main() {
+	vec4 offset = fma(smaa.rt_metrics.xyxy, vec4(1.0, 0.0, 0.0, 1.0), uv_interp.xyxy);
+
+	vec4 a;
+	a.x = textureLod(blend_tex, offset.xy, 0.0).a; // Right.
+	a.y = textureLod(blend_tex, offset.zw, 0.0).g; // Bottom.
+	a.wz = textureLod(blend_tex, uv_interp, 0.0).xz; // Top / Left.
+
+	if (dot(a, vec4(1.0)) < 1e-5) {
+		frag_color = textureLod(source_color, uv_interp, 0.0);
+		return;
+	}
+
+	// max(horizontal) > max(vertical).
+	bool h = max(a.x, a.z) > max(a.y, a.w);
+	vec4 blending_offset = h ? vec4(a.x, 0.0, a.z, 0.0) : vec4(0.0, a.y, 0.0, a.w);
+	vec2 blending_weight = h ? a.xz : a.yw;
+	blending_weight /= dot(blending_weight, vec2(1.0));
+
+	vec4 blending_coord = fma(blending_offset, vec4(smaa.rt_metrics.xy, -smaa.rt_metrics.xy), uv_interp.xyxy);
+	frag_color = blending_weight.x * textureLod(source_color, blending_coord.xy, 0.0);
+	frag_color += blending_weight.y * textureLod(source_color, blending_coord.zw, 0.0);
+}
@@ -0,0 +1,66 @@
+/* clang-format off */
+#[vertex]
+
+#version 450
+
+layout(location = 0) out vec2 uv_interp;
+/* clang-format on */
+
+void main() {
+	vec2 vertex_base;
+	if (gl_VertexIndex == 0) {
+		vertex_base = vec2(-1.0, -1.0);
+	} else if (gl_VertexIndex == 1) {
+		vertex_base = vec2(-1.0, 3.0);
+	} else {
+		vertex_base = vec2(3.0, -1.0);
+	}
+	gl_Position = vec4(vertex_base, 0.0, 1.0);
+	uv_interp = clamp(vertex_base, vec2(0.0, 0.0), vec2(1.0, 1.0)) * 2.0; // saturate(x) * 2.0
+}
+
+/* clang-format off */
+#[fragment]
+
+#version 450
+
+layout(push_constant, std430) uniform Streak {
+    vec2 source_pixel_size; // 08 - 08
+    // Direction in source pixels, scaled by the tap spacing of this pass.
+    vec2 step; // 08 - 16
+
+    // Attenuation from one tap to the next.
+    float tap_attenuation; // 04 - 20
+    float pad1; // 04 - 24
+    vec2 pad2; // 08 - 32
+
+    // Tint times intensity, white except for the last pass.
+    vec3 tint; // 12 - 44
+    float pad3; // 04 - 48
+}
+streak;
+
+layout(location = 0) in vec2 uv_interp;
+/* clang-format on */
+
+layout(set = 0, binding = 0) uniform sampler2D source_color;
+
+layout(location = 0) out vec4 frag_color;
+
+#define STREAK_TAPS 4
+
+// One pass of Kawase's streak filter, extended to both sides. Each pass spaces its
+// taps four times further apart than the previous one, so a few passes reach far.
+void main() {
+	vec2 step = streak.step * streak.source_pixel_size;
+	vec3 color = textureLod(source_color, uv_interp, 0.0).rgb;
+	float weight_sum = 1.0;
+	float weight = 1.0;
+	for (int i = 1; i < STREAK_TAPS; i++) {
+		weight *= streak.tap_attenuation;
+		color += textureLod(source_color, uv_interp + step * float(i), 0.0).rgb * weight;
+		color += textureLod(source_color, uv_interp - step * float(i), 0.0).rgb * weight;
+		weight_sum += 2.0 * weight;
+	}
+	frag_color = vec4(color / weight_sum * streak.tint, 1.0);
+}
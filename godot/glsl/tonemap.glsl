@@ -44,6 +44,69 @@ layout(set = 0, binding = 0) uniform SAMPLER_FORMAT source_color;
 
 layout(set = 1, binding = 0) uniform SAMPLER_FORMAT source_glow;
 layout(set = 1, binding = 1) uniform sampler2D glow_map;
+layout(set = 1, binding = 2) uniform sampler2D lens_dirt;
+// A single wide glow level, lighting up the dirt.
+layout(set = 1, binding = 3) uniform sampler2D lens_dirt_glow;
+layout(set = 1, binding = 4) uniform sampler2D streak;
+layout(set = 1, binding = 5) uniform sampler2D lens_flare;
+layout(set = 1, binding = 6) uniform sampler2D vignette_mask;
+layout(set = 1, binding = 7) uniform sampler2D grain_texture;
+// Weights of the chromatic aberration samples, from the center to the outer sample.
+layout(set = 1, binding = 8) uniform sampler2D spectral_lut;
+
+layout(set = 2, binding = 0) uniform sampler3D color_lut;
+layout(set = 2, binding = 1) uniform sampler1D custom_curve;
+layout(set = 2, binding = 2) uniform sampler2D color_correction_1d;
+layout(set = 2, binding = 3) uniform sampler3D color_correction;
+
+layout(set = 3, binding = 0, std140) uniform Grading {
+	// Rows of the white balance and channel mixer matrix, applied in linear space.
+	vec4 color_matrix[3];
+	vec4 lift;
+	vec4 inv_gamma;
+	vec4 gain;
+	// w: saturation.
+	vec4 cdl_slope;
+	vec4 cdl_offset;
+	vec4 cdl_power;
+}
+grading;
+
+layout(set = 3, binding = 1, std140) uniform Effects {
+	// a: opacity.
+	vec4 vignette_color;
+	vec2 vignette_center;
+	float vignette_scale;
+	float vignette_smoothness;
+	float vignette_roundness;
+	float grain_intensity;
+	float grain_size;
+	float grain_response;
+	// One step of the output encoding.
+	float debanding_scale;
+	float chromatic_aberration_amount;
+	int chromatic_aberration_samples;
+	float lens_distortion_intensity;
+	// Zoom compensating the cropped corners.
+	float lens_distortion_scale;
+	// CAS peak of the sharpening inside FXAA.
+	float fxaa_sharpen_peak;
+	float fxaa_edge_threshold_min;
+	float fxaa_edge_threshold_max;
+	float fxaa_subpixel_quality;
+	// Number of edge search steps of the quality preset.
+	int fxaa_iterations;
+	float pad0;
+	float pad1;
+	vec4 fxaa_steps[3];
+	// Display peak relative to paper white at 1.0.
+	float output_peak;
+	// Paper white in scRGB or PQ luminance units.
+	float output_scale;
+	float pad2;
+	float pad3;
+}
+effects;
 
 layout(constant_id = 0) const bool use_glow_map = false;
 layout(constant_id = 1) const bool use_fxaa = false;
@@ -55,10 +118,51 @@ layout(constant_id = 5) const bool tonemapper_aces = false;
 layout(constant_id = 6) const bool tonemapper_agx = false;
 layout(constant_id = 7) const bool tonemapper_gt = false;
 layout(constant_id = 8) const bool tonemapper_lottes = false;
+layout(constant_id = 9) const bool tonemapper_pbr_neutral = false;
+layout(constant_id = 10) const bool tonemapper_hable = false;
+layout(constant_id = 11) const bool tonemapper_custom = false;
+
+layout(constant_id = 12) const bool glow_mode_add = false;
+layout(constant_id = 13) const bool glow_mode_replace = false;
+layout(constant_id = 14) const bool glow_mode_mix = false;
+layout(constant_id = 15) const bool glow_mode_screen = false;
+layout(constant_id = 16) const bool glow_mode_softlight = false;
+
+layout(constant_id = 17) const bool use_color_lut = false;
+
+layout(constant_id = 18) const bool tonemap_mode_per_channel = false;
+layout(constant_id = 19) const bool tonemap_mode_max_rgb = false;
+layout(constant_id = 20) const bool tonemap_mode_luminance = false;
+
+layout(constant_id = 21) const bool use_bcs = false;
+layout(constant_id = 22) const bool use_color_correction = false;
+layout(constant_id = 23) const bool use_1d_color_correction = false;
+
+layout(constant_id = 24) const bool use_grading = false;
+layout(constant_id = 25) const bool grading_acescc = false;
+layout(constant_id = 26) const bool grading_after_tonemap = false;
+
+layout(constant_id = 27) const bool use_lens_dirt = false;
+layout(constant_id = 28) const bool use_streak = false;
+layout(constant_id = 29) const bool use_lens_flare = false;
+
+layout(constant_id = 30) const bool use_vignette = false;
+layout(constant_id = 31) const bool vignette_after_tonemap = false;
+
+layout(constant_id = 32) const bool use_grain = false;
+layout(constant_id = 33) const bool use_grain_texture = false;
 
-layout(constant_id = 9) const bool glow_mode_add = false;
-layout(constant_id = 10) const bool glow_mode_replace = false;
-layout(constant_id = 11) const bool glow_mode_mix = false;
+layout(constant_id = 34) const bool use_debanding = false;
+
+layout(constant_id = 35) const bool use_chromatic_aberration = false;
+layout(constant_id = 36) const bool use_spectral_lut = false;
+layout(constant_id = 37) const bool use_lens_distortion = false;
+
+layout(constant_id = 38) const bool use_fxaa_sharpen = false;
+
+layout(constant_id = 39) const bool output_scrgb = false;
+layout(constant_id = 40) const bool output_hdr10 = false;
+const bool output_hdr = output_scrgb || output_hdr10;
 
 layout(push_constant, std430) uniform Params {
 	vec2 dest_pixel_size;
@@ -68,6 +172,18 @@ layout(push_constant, std430) uniform Params {
 	float glow_map_strength;
 	float exposure;
 	float white;
+
+	vec3 color_lut_domain_min;
+	float tonemap_mode_blend;
+	vec3 color_lut_domain_scale;
+	float lens_dirt_intensity;
+
+	// Parameters of the selected tonemapper, see PostEffectToneMap::tonemap_params.
+	vec4 tonemap_params[3];
+
+	// Brightness, contrast, saturation.
+	vec3 bcs;
+	uint frame;
 }
 params;
 
@@ -137,19 +253,35 @@ layout(location = 0) out vec4 frag_color;
 //
 //----------------------------------------------------------------------------------
 
-float QUALITY(float q) {
-	return (q < 5 ? 1.0 : (q > 5 ? (q < 10 ? 2.0 : (q < 11 ? 4.0 : 8.0)) : 1.5));
+// Step length of the quality preset, FXAA_QUALITY__P0 to P11.
+float QUALITY(int q) {
+	return effects.fxaa_steps[q >> 2][q & 3];
 }
 
 float rgb2luma(vec3 rgb) {
 	return sqrt(dot(rgb, vec3(0.299, 0.587, 0.114)));
 }
 
+// Contrast adaptive sharpening on the lumas FXAA already fetched, for pixels without an edge.
+vec3 sharpen_fxaa_luma(vec3 color, float lumaCenter, vec4 lumaCross) {
+	// The lumas are unbounded before tonemapping, compress them reversibly.
+	vec4 n = lumaCross / (1.0 + lumaCross);
+	float e = lumaCenter / (1.0 + lumaCenter);
+	float mn = min(e, min(min(n.x, n.y), min(n.z, n.w)));
+	float mx = max(e, max(max(n.x, n.y), max(n.z, n.w)));
+	float amp = sqrt(clamp(min(mn, 1.0 - mx) / max(mx, 1e-5), 0.0, 1.0));
+	float w = amp * effects.fxaa_sharpen_peak;
+	float sharpened = clamp((dot(n, vec4(1.0)) * w + e) / (1.0 + 4.0 * w), 0.0, 0.999);
+	// rgb2luma() is the square root of the luminance.
+	float ratio = sharpened / (1.0 - sharpened) / max(lumaCenter, 1e-5);
+	return color * ratio * ratio;
+}
+
 vec3 do_fxaa(vec3 color, float exposure, vec2 uv_interp) {
-	const float EDGE_THRESHOLD_MIN = 0.0312;
-	const float EDGE_THRESHOLD_MAX = 0.125;
-	const int ITERATIONS = 12;
-	const float SUBPIXEL_QUALITY = 0.75;
+	float EDGE_THRESHOLD_MIN = effects.fxaa_edge_threshold_min;
+	float EDGE_THRESHOLD_MAX = effects.fxaa_edge_threshold_max;
+	int ITERATIONS = effects.fxaa_iterations;
+	float SUBPIXEL_QUALITY = effects.fxaa_subpixel_quality;
 
 #ifdef USE_MULTIVIEW
 	float lumaUp = rgb2luma(textureLodOffset(source_color, vec3(uv_interp, ViewIndex), 0.0, ivec2(0, 1)).xyz * exposure);
@@ -165,6 +297,9 @@ vec3 do_fxaa(vec3 color, float exposure, vec2 uv_interp) {
 	float lumaRange = lumaMax - lumaMin;
 
 	if (lumaRange < max(EDGE_THRESHOLD_MIN, lumaMax * EDGE_THRESHOLD_MAX)) {
+		if (use_fxaa_sharpen) {
+			return sharpen_fxaa_luma(color, lumaCenter, vec4(lumaUp, lumaDown, lumaLeft, lumaRight));
+		}
 		return color;
 	}
 
@@ -309,6 +444,9 @@ vec3 do_fxaa(vec3 color, float exposure, vec2 uv_interp) {
 	float lumaRange = lumaMax - lumaMin;
 
 	if (lumaRange < max(EDGE_THRESHOLD_MIN, lumaMax * EDGE_THRESHOLD_MAX)) {
+		if (use_fxaa_sharpen) {
+			return sharpen_fxaa_luma(color, lumaCenter, vec4(lumaUp, lumaDown, lumaLeft, lumaRight));
+		}
 		return color;
 	}
 
@@ -511,10 +649,18 @@ vec3 agx_contrast_approx(vec3 x) {
 	return 0.021 * x + 4.0111 * x2 - 25.682 * x2 * x + 70.359 * x4 - 74.778 * x4 * x + 27.069 * x4 * x2;
 }
 
+// ASC CDL based look applied to the sigmoid output, matching Blender's AgX looks.
+// See https://iolite-engine.com/blog_posts/minimal_agx_implementation
+vec3 agx_look(vec3 color, vec3 offset, vec3 slope, vec3 power, float saturation) {
+	float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+	color = pow(max(color * slope + offset, vec3(0.0)), power);
+	return luma + saturation * (color - luma);
+}
+
 // This is an approximation and simplification of EaryChow's AgX implementation that is used by Blender.
 // This code is based off of the script that generates the AgX_Base_sRGB.cube LUT that Blender uses.
 // Source: https://github.com/EaryChow/AgX_LUT_Gen/blob/main/AgXBasesRGB.py
-vec3 tonemap_agx(vec3 color) {
+vec3 tonemap_agx(vec3 color, float contrast, vec3 look_offset, vec3 look_slope, vec3 look_power, float look_saturation) {
 	// Combined linear sRGB to linear Rec 2020 and Blender AgX inset matrices:
 	const mat3 srgb_to_rec2020_agx_inset_matrix = mat3(
 			0.54490813676363087053, 0.14044005884001287035, 0.088827411851915368603,
@@ -557,9 +703,15 @@ vec3 tonemap_agx(vec3 color) {
 	color = clamp(log2(color), min_ev, max_ev);
 	color = (color - min_ev) / (max_ev - min_ev);
 
+	// Scale the log encoding around middle gray to adjust the base contrast.
+	const float middle_gray = (log2(0.18) - min_ev) / (max_ev - min_ev);
+	color = clamp((color - middle_gray) * contrast + middle_gray, 0.0, 1.0);
+
 	// Apply sigmoid function approximation.
 	color = agx_contrast_approx(color);
 
+	color = agx_look(color, look_offset, look_slope, look_power, look_saturation);
+
 	// Convert back to linear before applying outset matrix.
 	color = pow(color, vec3(2.4));
 
@@ -572,6 +724,10 @@ vec3 tonemap_agx(vec3 color) {
 	return color;
 }
 
+vec3 tonemap_agx(vec3 color) {
+	return tonemap_agx(color, params.tonemap_params[1].w, params.tonemap_params[1].xyz, params.tonemap_params[0].xyz, params.tonemap_params[2].xyz, params.tonemap_params[0].w);
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Uchimura 2017, "HDR theory and practice"
 // Math: https://www.desmos.com/calculator/gslcdxvipg
@@ -597,36 +753,72 @@ vec3 tonemap_gt(vec3 x, float P, float a, float m, float l, float c, float b) {
 }
 
 vec3 tonemap_gt(vec3 x) {
-	const float P = 1.0; // max display brightness
-	const float a = 1.0; // contrast
-	const float m = 0.22; // linear section start
-	const float l = 0.4; // linear section length
-	const float c = 1.33; // black
-	const float b = 0.0; // pedestal
-
-	return tonemap_gt(x, P, a, m, l, c, b);
+	return tonemap_gt(x, params.tonemap_params[0].x, params.tonemap_params[0].y, params.tonemap_params[0].z, params.tonemap_params[0].w, params.tonemap_params[1].x, params.tonemap_params[1].y);
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 // Lottes 2016, "Advanced Techniques and Optimization of HDR Color Pipelines"
+// a: contrast, d: shoulder, b and c are derived from hdrMax, midIn and midOut on the CPU.
+vec3 tonemap_lottes(vec3 x, float a, float d, float b, float c) {
+	return pow(x, vec3(a)) / (pow(x, vec3(a * d)) * b + c);
+}
+
 vec3 tonemap_lottes(vec3 x) {
-	const vec3 a = vec3(1.6);
-	const vec3 d = vec3(0.977);
-	const vec3 hdrMax = vec3(8.0);
-	const vec3 midIn = vec3(0.18);
-	const vec3 midOut = vec3(0.267);
+	return tonemap_lottes(x, params.tonemap_params[0].x, params.tonemap_params[0].y, params.tonemap_params[0].z, params.tonemap_params[0].w);
+}
 
-	const vec3 b =
-			(-pow(midIn, a) + pow(hdrMax, a) * midOut) /
-			((pow(hdrMax, a * d) - pow(midIn, a * d)) * midOut);
-	const vec3 c =
-			(pow(hdrMax, a * d) * pow(midIn, a) - pow(hdrMax, a) * pow(midIn, a * d) * midOut) /
-			((pow(hdrMax, a * d) - pow(midIn, a * d)) * midOut);
+// Brightest value the tonemappers output, 1.0 is paper white.
+float output_peak() {
+	return output_hdr ? effects.output_peak : 1.0;
+}
 
-	return pow(x, a) / (pow(x, a * d) * b + c);
+// Khronos PBR Neutral, https://github.com/KhronosGroup/ToneMapping/tree/main/PBR_Neutral
+// The shoulder rolls off towards `display_peak` instead of 1.0, below it the curve is unchanged.
+vec3 tonemap_pbr_neutral(vec3 color, float display_peak) {
+	const float start_compression = 0.8 - 0.04;
+	const float desaturation = 0.15;
+
+	float x = min(color.r, min(color.g, color.b));
+	float offset = x < 0.08 ? x - 6.25 * x * x : 0.04;
+	color -= offset;
+
+	float peak = max(color.r, max(color.g, color.b));
+	if (peak < start_compression) {
+		return color;
+	}
+
+	float d = display_peak - start_compression;
+	float new_peak = display_peak - d * d / (peak + d - start_compression);
+	color *= new_peak / peak;
+
+	float g = 1.0 - 1.0 / (desaturation * (peak - new_peak) + 1.0);
+	return mix(color, vec3(new_peak), g);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Hable 2010, "Filmic Tonemapping Operators" (Uncharted 2)
+// A: shoulder strength, B: linear strength, C: linear angle,
+// D: toe strength, E: toe numerator, F: toe denominator.
+vec3 tonemap_hable(vec3 x, float A, float B, float C, float D, float E, float F, float inv_white_tonemapped) {
+	vec3 color_tonemapped = ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F;
+	return color_tonemapped * inv_white_tonemapped;
+}
+
+vec3 tonemap_hable(vec3 x) {
+	return tonemap_hable(x, params.tonemap_params[0].x, params.tonemap_params[0].y, params.tonemap_params[0].z, params.tonemap_params[0].w, params.tonemap_params[1].x, params.tonemap_params[1].y, params.tonemap_params[1].z);
+}
+
+// User curve baked into a 1D texture, its x axis covers [min_ev, max_ev] of log2(color).
+vec3 tonemap_custom(vec3 color) {
+	float min_ev = params.tonemap_params[0].x;
+	float inv_ev_range = params.tonemap_params[0].y;
+	vec3 t = clamp((log2(max(color, vec3(1e-10))) - min_ev) * inv_ev_range, vec3(0.0), vec3(1.0));
+	float size = float(textureSize(custom_curve, 0));
+	t = t * ((size - 1.0) / size) + 0.5 / size;
+	return vec3(textureLod(custom_curve, t.r, 0.0).r, textureLod(custom_curve, t.g, 0.0).g, textureLod(custom_curve, t.b, 0.0).b);
 }
 
-vec3 apply_tonemapping(vec3 color, float white) { // inputs are LINEAR
+vec3 apply_tonemapping_curve(vec3 color, float white) { // inputs are LINEAR
 	// Ensure color values passed to tonemappers are positive.
 	// They can be negative in the case of negative lights, which leads to undesired behavior.
 	if (tonemapper_reinhard) {
@@ -641,38 +833,317 @@ vec3 apply_tonemapping(vec3 color, float white) { // inputs are LINEAR
 		return tonemap_gt(color);
 	} else if (tonemapper_lottes) {
 		return tonemap_lottes(color);
+	} else if (tonemapper_pbr_neutral) {
+		return tonemap_pbr_neutral(max(vec3(0.0f), color), output_peak());
+	} else if (tonemapper_hable) {
+		return tonemap_hable(max(vec3(0.0f), color));
+	} else if (tonemapper_custom) {
+		return tonemap_custom(color);
 	}
 	// Linear
 	return color;
 }
 
+vec3 apply_tonemapping(vec3 color, float white) {
+	// GT, Lottes and PBR Neutral take the peak as a parameter. Reinhard is stretched to it,
+	// which keeps its slope of 1.0 at black and with it mid grey, and moves its white to
+	// `white * peak`. The other curves have a toe that stretching would push mid grey into,
+	// they stay SDR-referred and never exceed paper white.
+	if (output_hdr && tonemapper_reinhard) {
+		float peak = output_peak();
+		return apply_tonemapping_curve(color / peak, white) * peak;
+	}
+	return apply_tonemapping_curve(color, white);
+}
+
+vec3 linear_to_srgb(vec3 color) {
+	// Not clamped to 1.0, LUT domains may extend above it.
+	color = max(color, vec3(0.0));
+	const vec3 a = vec3(0.055f);
+	return mix((vec3(1.0f) + a) * pow(color.rgb, vec3(1.0f / 2.4f)) - a, 12.92f * color.rgb, lessThan(color.rgb, vec3(0.0031308f)));
+}
+
+vec3 srgb_to_linear(vec3 color) {
+	return mix(pow((color.rgb + vec3(0.055)) * (1.0 / (1.0 + 0.055)), vec3(2.4)), color.rgb * (1.0 / 12.92), lessThan(color.rgb, vec3(0.04045)));
+}
+
+// .cube LUTs exported from grading tools expect display-encoded input.
+vec3 apply_color_lut(vec3 color) {
+	vec3 uvw = clamp((linear_to_srgb(color) - params.color_lut_domain_min) * params.color_lut_domain_scale, vec3(0.0), vec3(1.0));
+	// Remap to texel centers so the lattice end points are hit exactly.
+	float size = float(textureSize(color_lut, 0).x);
+	uvw = uvw * ((size - 1.0) / size) + 0.5 / size;
+	return srgb_to_linear(textureLod(color_lut, uvw, 0.0).rgb);
+}
+
+// Applies the tonemapper per channel, to the max RGB component or to the luminance, and
+// blends the result with the per-channel one. The latter two keep the hue of saturated colors.
+vec3 apply_tonemapping_mode(vec3 color, float white) {
+	vec3 per_channel = apply_tonemapping(color, white);
+	if (tonemap_mode_max_rgb) {
+		color = max(color, vec3(0.0));
+		float peak = max(color.r, max(color.g, color.b));
+		vec3 mapped_peak = apply_tonemapping(vec3(peak), white);
+		vec3 mapped = color * (max(mapped_peak.r, max(mapped_peak.g, mapped_peak.b)) / max(peak, 1e-6));
+		return mix(per_channel, mapped, params.tonemap_mode_blend);
+	} else if (tonemap_mode_luminance) {
+		const vec3 luminance_weights = vec3(0.2126, 0.7152, 0.0722);
+		color = max(color, vec3(0.0));
+		float luminance = dot(color, luminance_weights);
+		float mapped_luminance = dot(apply_tonemapping(vec3(luminance), white), luminance_weights);
+		vec3 mapped = color * (mapped_luminance / max(luminance, 1e-6));
+		// Saturation rolloff: desaturate towards gray just enough to bring the brightest
+		// channel back to the display peak, instead of clipping it and shifting the hue.
+		float peak = max(mapped.r, max(mapped.g, mapped.b));
+		float display_peak = output_peak();
+		if (peak > display_peak) {
+			mapped = mix(mapped, vec3(mapped_luminance), clamp((peak - display_peak) / max(peak - mapped_luminance, 1e-6), 0.0, 1.0));
+		}
+		return mix(per_channel, mapped, params.tonemap_mode_blend);
+	}
+	return per_channel;
+}
+
+// Matrices are built from rows, so they must be applied as `v * M`.
+const mat3 REC709_TO_AP1 = mat3(
+		vec3(0.61319, 0.33951, 0.04737),
+		vec3(0.07021, 0.91634, 0.01345),
+		vec3(0.02062, 0.10957, 0.86961));
+const mat3 AP1_TO_REC709 = mat3(
+		vec3(1.70505, -0.62179, -0.08326),
+		vec3(-0.13026, 1.14080, -0.01055),
+		vec3(-0.02400, -0.12897, 1.15297));
+
+const float HALF_MAX = 65504.0;
+
+vec3 linear_to_acescc(vec3 color) {
+	color = clamp(color, vec3(0.0), vec3(HALF_MAX));
+	return mix((log2(0.00001525878 + color * 0.5) + 9.72) / 17.52, (log2(color) + 9.72) / 17.52, greaterThanEqual(color, vec3(0.00003051757)));
+}
+
+vec3 acescc_to_linear(vec3 color) {
+	vec3 low = (exp2(color * 17.52 - 9.72) - 0.00001525878) * 2.0;
+	vec3 high = min(exp2(color * 17.52 - 9.72), vec3(HALF_MAX));
+	return mix(low, high, greaterThanEqual(color, vec3(-0.3013698630)));
+}
+
+vec3 apply_grading(vec3 color) {
+	color = color * mat3(grading.color_matrix[0].xyz, grading.color_matrix[1].xyz, grading.color_matrix[2].xyz);
+	if (grading_acescc) {
+		color = linear_to_acescc(color * REC709_TO_AP1);
+	}
+
+	// Lift, gamma, gain.
+	color = grading.gain.rgb * (color + grading.lift.rgb * (1.0 - color));
+	color = sign(color) * pow(abs(color), grading.inv_gamma.rgb);
+
+	// ASC CDL.
+	color = pow(max(color * grading.cdl_slope.rgb + grading.cdl_offset.rgb, vec3(0.0)), grading.cdl_power.rgb);
+	float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+	color = luma + grading.cdl_slope.w * (color - luma);
+
+	if (grading_acescc) {
+		color = acescc_to_linear(color) * AP1_TO_REC709;
+	}
+	return color;
+}
+
+// Same as Godot's tonemap.glsl, expects sRGB-encoded colors.
+vec3 apply_bcs(vec3 color, vec3 bcs) {
+	color = mix(vec3(0.0f), color, bcs.x);
+	color = mix(vec3(0.5f), color, bcs.y);
+	color = mix(vec3(dot(vec3(1.0f), color) * 0.33333f), color, bcs.z);
+	return color;
+}
+
+vec3 apply_color_correction(vec3 color) {
+	if (use_1d_color_correction) {
+		color.r = texture(color_correction_1d, vec2(color.r, 0.0f)).r;
+		color.g = texture(color_correction_1d, vec2(color.g, 0.0f)).g;
+		color.b = texture(color_correction_1d, vec2(color.b, 0.0f)).b;
+		return color;
+	}
+	return textureLod(color_correction, color, 0.0).rgb;
+}
+
 #ifdef USE_MULTIVIEW
-vec3 gather_glow() {
-	vec2 texel = gl_FragCoord.xy * 0.25;
+vec3 gather_glow(vec2 screen_uv) {
+	vec2 texel = screen_uv / params.dest_pixel_size * 0.25;
 	vec2 uv = texel * params.glow_pixel_size;
 	return textureLod(source_glow, vec3(uv, ViewIndex), 0.0).rgb;
 }
+
+vec3 sample_source(vec2 uv) {
+	return textureLod(source_color, vec3(uv, ViewIndex), 0.0).rgb;
+}
 #else
-vec3 gather_glow() {
-	vec2 texel = gl_FragCoord.xy * 0.25;
+vec3 gather_glow(vec2 screen_uv) {
+	vec2 texel = screen_uv / params.dest_pixel_size * 0.25;
 	vec2 uv = texel * params.glow_pixel_size;
 	return textureLod(source_glow, uv, 0.0).rgb;
 }
+
+vec3 sample_source(vec2 uv) {
+	return textureLod(source_color, uv, 0.0).rgb;
+}
 #endif // !USE_MULTIVIEW
 
 vec3 apply_glow(vec3 color, vec3 glow) { // apply glow using the selected blending mode
 	if (glow_mode_add) {
 		return color + glow;
+	} else if (glow_mode_screen) {
+		// Needs color clamping.
+		glow.rgb = clamp(glow.rgb, vec3(0.0f), vec3(1.0f));
+		return max((color + glow) - (color * glow), vec3(0.0));
+	} else if (glow_mode_softlight) {
+		// Needs color clamping.
+		glow.rgb = clamp(glow.rgb, vec3(0.0f), vec3(1.0f));
+		glow = glow * vec3(0.5f) + vec3(0.5f);
+
+		color.r = (glow.r <= 0.5f) ? (color.r - (1.0f - 2.0f * glow.r) * color.r * (1.0f - color.r)) : (((glow.r > 0.5f) && (color.r <= 0.25f)) ? (color.r + (2.0f * glow.r - 1.0f) * (4.0f * color.r * (4.0f * color.r + 1.0f) * (color.r - 1.0f) + 7.0f * color.r)) : (color.r + (2.0f * glow.r - 1.0f) * (sqrt(color.r) - color.r)));
+		color.g = (glow.g <= 0.5f) ? (color.g - (1.0f - 2.0f * glow.g) * color.g * (1.0f - color.g)) : (((glow.g > 0.5f) && (color.g <= 0.25f)) ? (color.g + (2.0f * glow.g - 1.0f) * (4.0f * color.g * (4.0f * color.g + 1.0f) * (color.g - 1.0f) + 7.0f * color.g)) : (color.g + (2.0f * glow.g - 1.0f) * (sqrt(color.g) - color.g)));
+		color.b = (glow.b <= 0.5f) ? (color.b - (1.0f - 2.0f * glow.b) * color.b * (1.0f - color.b)) : (((glow.b > 0.5f) && (color.b <= 0.25f)) ? (color.b + (2.0f * glow.b - 1.0f) * (4.0f * color.b * (4.0f * color.b + 1.0f) * (color.b - 1.0f) + 7.0f * color.b)) : (color.b + (2.0f * glow.b - 1.0f) * (sqrt(color.b) - color.b)));
+		return color;
 	} else { //replace
 		return glow;
 	}
 }
 
+vec3 apply_vignette(vec3 color) {
+	vec2 d = abs(uv_interp - 0.5 - effects.vignette_center) * effects.vignette_scale;
+	// Keep the shape round on screen.
+	d.x *= params.dest_pixel_size.y / params.dest_pixel_size.x;
+	d = pow(clamp(d, vec2(0.0), vec2(1.0)), vec2(effects.vignette_roundness));
+	float factor = pow(clamp(1.0 - dot(d, d), 0.0, 1.0), effects.vignette_smoothness);
+	factor *= texture(vignette_mask, uv_interp).r;
+	vec3 tint = mix(vec3(1.0), effects.vignette_color.rgb, effects.vignette_color.a);
+	return color * mix(tint, vec3(1.0), factor);
+}
+
+// https://www.reedbeta.com/blog/hash-functions-for-gpu-rendering/
+uint pcg_hash(uint v) {
+	uint state = v * 747796405u + 2891336453u;
+	uint word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+	return (word >> 22u) ^ word;
+}
+
+// Triangular distributed noise in [-1, 1], different every frame.
+float grain_hash(ivec2 cell) {
+	uint seed = pcg_hash(uint(cell.x) + pcg_hash(uint(cell.y) + pcg_hash(params.frame)));
+	float a = float(seed & 0xffffu) / 65535.0;
+	float b = float(seed >> 16u) / 65535.0;
+	return a + b - 1.0;
+}
+
+float grain_noise() {
+	vec2 coord = gl_FragCoord.xy / effects.grain_size;
+	if (use_grain_texture) {
+		// Jump to a random place of the tile every frame.
+		uint seed = pcg_hash(params.frame);
+		ivec2 size = textureSize(grain_texture, 0);
+		ivec2 offset = ivec2(seed & 0xffffu, seed >> 16u);
+		ivec2 texel = (ivec2(coord) + offset) % size;
+		return texelFetch(grain_texture, texel, 0).r * 2.0 - 1.0;
+	}
+	// Value noise, so grains larger than a pixel stay soft.
+	ivec2 cell = ivec2(floor(coord));
+	vec2 f = smoothstep(0.0, 1.0, fract(coord));
+	float n0 = mix(grain_hash(cell), grain_hash(cell + ivec2(1, 0)), f.x);
+	float n1 = mix(grain_hash(cell + ivec2(0, 1)), grain_hash(cell + ivec2(1, 1)), f.x);
+	return mix(n0, n1, f.y);
+}
+
+vec3 apply_grain(vec3 color) {
+	float luma = dot(clamp(color, vec3(0.0), vec3(1.0)), vec3(0.2126, 0.7152, 0.0722));
+	// Strongest in the shadows and midtones, like film.
+	float response = mix(1.0, 1.0 - sqrt(luma), effects.grain_response);
+	return max(color + color * grain_noise() * effects.grain_intensity * response, vec3(0.0));
+}
+
+// Radial Brown-Conrady distortion, positive intensities give barrel distortion.
+vec2 apply_lens_distortion(vec2 uv) {
+	float aspect = params.dest_pixel_size.y / params.dest_pixel_size.x;
+	// Normalized so that the corners are at radius 1.
+	vec2 p = (uv - 0.5) * 2.0 * vec2(aspect, 1.0) / length(vec2(aspect, 1.0));
+	float factor = 1.0 + effects.lens_distortion_intensity * dot(p, p);
+	return 0.5 + (uv - 0.5) * factor * effects.lens_distortion_scale;
+}
+
+vec3 spectrum(float t) {
+	if (use_spectral_lut) {
+		return textureLod(spectral_lut, vec2(t, 0.5), 0.0).rgb;
+	}
+	// Red, green, blue tents.
+	return clamp(vec3(1.0 - 2.0 * t, 1.0 - abs(2.0 * t - 1.0), 2.0 * t - 1.0), vec3(0.0), vec3(1.0));
+}
+
+// Spreads the spectrum towards the screen center, more at the borders.
+vec3 apply_chromatic_aberration(vec2 uv) {
+	vec2 coords = 2.0 * uv - 1.0;
+	vec2 delta = -coords * dot(coords, coords) * effects.chromatic_aberration_amount;
+	int samples = effects.chromatic_aberration_samples;
+	vec3 sum = vec3(0.0);
+	vec3 weight_sum = vec3(0.0);
+	for (int i = 0; i < samples; i++) {
+		float t = (float(i) + 0.5) / float(samples);
+		vec3 weight = spectrum(t);
+		sum += sample_source(uv + delta * t) * weight;
+		weight_sum += weight;
+	}
+	return sum / max(weight_sum, vec3(1e-4));
+}
+
+// http://www.iryoku.com/next-generation-post-processing-in-call-of-duty-advanced-warfare
+float interleaved_gradient_noise(vec2 pos) {
+	return fract(52.9829189 * fract(dot(pos, vec2(0.06711056, 0.00583715))));
+}
+
+// Triangular PDF dither of one output step, applied in the encoding the output is quantized in.
+// Disabled for scRGB output, which stays linear float.
+vec3 apply_debanding(vec3 color) {
+	vec2 pos = gl_FragCoord.xy + 5.588238 * float(params.frame % 64u);
+	float noise = interleaved_gradient_noise(pos) + interleaved_gradient_noise(pos + vec2(17.0, 113.0)) - 1.0;
+	if (output_hdr10) {
+		// Already PQ encoded.
+		return clamp(color + noise * effects.debanding_scale, vec3(0.0), vec3(1.0));
+	}
+	color = linear_to_srgb(max(color, vec3(0.0)));
+	color += noise * effects.debanding_scale;
+	return srgb_to_linear(max(color, vec3(0.0)));
+}
+
+// Keep the constants below in sync with hdr.rs, its tests compare them.
+
+// BT.2087, applied as `v * M` like the other matrices.
+const mat3 REC709_TO_REC2020 = mat3(
+		vec3(0.627404, 0.329283, 0.043313),
+		vec3(0.069097, 0.919540, 0.011362),
+		vec3(0.016391, 0.088013, 0.895595));
+
+// SMPTE ST 2084 constants, exact in binary.
+const float PQ_M1 = 0.1593017578125;
+const float PQ_M2 = 78.84375;
+const float PQ_C1 = 0.8359375;
+const float PQ_C2 = 18.8515625;
+const float PQ_C3 = 18.6875;
+
+// Port of hdr::pq_encode, `color` is luminance relative to 10000 nits.
+vec3 linear_to_pq(vec3 color) {
+	vec3 y = pow(clamp(color, vec3(0.0), vec3(1.0)), vec3(PQ_M1));
+	return pow((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y), vec3(PQ_M2));
+}
+
 void main() {
+	// Lens effects distort the scene, but not what's on the lens itself (dirt and vignette).
+	vec2 uv = uv_interp;
+	if (use_lens_distortion) {
+		uv = apply_lens_distortion(uv);
+	}
+
 #if defined(USE_MULTIVIEW)
-	vec4 color = textureLod(source_color, vec3(uv_interp, ViewIndex), 0.0f);
+	vec4 color = textureLod(source_color, vec3(uv, ViewIndex), 0.0f);
 #else
-	vec4 color = textureLod(source_color, uv_interp, 0.0f);
+	vec4 color = textureLod(source_color, uv, 0.0f);
 #endif
 
 	// Exposure
@@ -682,24 +1153,101 @@ void main() {
 
 	if (use_fxaa) {
 		// FXAA must be performed before glow to preserve the "bleed" effect of glow.
-		color.rgb = do_fxaa(color.rgb, exposure, uv_interp);
+		color.rgb = do_fxaa(color.rgb, exposure, uv);
+	}
+
+	if (use_chromatic_aberration) {
+		// Added on top, so that FXAA still applies to the center sample.
+		color.rgb += (apply_chromatic_aberration(uv) - sample_source(uv)) * exposure;
 	}
 
 	if (glow_mode_mix) {
-		vec3 glow = gather_glow();
+		vec3 glow = gather_glow(uv);
 		if (use_glow_map) {
 			glow = mix(glow, texture(glow_map, uv_interp).rgb * glow, params.glow_map_strength);
 		}
 		color.rgb = mix(color.rgb, glow, params.glow_intensity);
-	} else {
-		vec3 glow = gather_glow() * params.glow_intensity;
+	} else if (!glow_mode_screen && !glow_mode_softlight) {
+		vec3 glow = gather_glow(uv) * params.glow_intensity;
 		if (use_glow_map) {
 			glow = mix(glow, texture(glow_map, uv_interp).rgb * glow, params.glow_map_strength);
 		}
 		color.rgb = apply_glow(color.rgb, glow);
 	}
 
-	color.rgb = apply_tonemapping(color.rgb, params.white);
+	if (use_streak) {
+		color.rgb += textureLod(streak, uv, 0.0).rgb;
+	}
+
+	if (use_lens_flare) {
+		color.rgb += textureLod(lens_flare, uv, 0.0).rgb;
+	}
+
+	if (use_lens_dirt) {
+		vec3 dirt_glow = textureLod(lens_dirt_glow, uv, 0.0).rgb;
+		color.rgb += texture(lens_dirt, uv_interp).rgb * dirt_glow * params.lens_dirt_intensity;
+	}
+
+	if (use_vignette && !vignette_after_tonemap) {
+		// Physical falloff, scales the scene light before tonemapping.
+		color.rgb = apply_vignette(color.rgb);
+	}
+
+	if (use_grading && !grading_after_tonemap) {
+		color.rgb = apply_grading(color.rgb);
+	}
+
+	color.rgb = apply_tonemapping_mode(color.rgb, params.white);
+
+	if (glow_mode_screen || glow_mode_softlight) {
+		// Like Godot, these blend modes work on tonemapped, sRGB-encoded colors.
+		vec3 glow = gather_glow(uv) * params.glow_intensity;
+		if (use_glow_map) {
+			glow = mix(glow, texture(glow_map, uv_interp).rgb * glow, params.glow_map_strength);
+		}
+		glow = apply_tonemapping(glow, params.white);
+		color.rgb = srgb_to_linear(apply_glow(linear_to_srgb(max(color.rgb, vec3(0.0))), linear_to_srgb(glow)));
+	}
+
+	if (use_grading && grading_after_tonemap) {
+		color.rgb = apply_grading(color.rgb);
+	}
+
+	if (use_color_lut) {
+		color.rgb = apply_color_lut(color.rgb);
+	}
+
+	// Adjustments, applied in sRGB space like Godot does.
+	if (use_bcs || use_color_correction) {
+		color.rgb = linear_to_srgb(color.rgb);
+		if (use_bcs) {
+			color.rgb = apply_bcs(color.rgb, params.bcs);
+		}
+		if (use_color_correction) {
+			// apply_color_correction() expects a clamped, sRGB-encoded input.
+			color.rgb = clamp(color.rgb, vec3(0.0), vec3(1.0));
+			color.rgb = apply_color_correction(color.rgb);
+		}
+		color.rgb = srgb_to_linear(color.rgb);
+	}
+
+	if (use_vignette && vignette_after_tonemap) {
+		color.rgb = apply_vignette(color.rgb);
+	}
+
+	if (use_grain) {
+		color.rgb = apply_grain(color.rgb);
+	}
+
+	if (output_scrgb) {
+		color.rgb *= effects.output_scale;
+	} else if (output_hdr10) {
+		color.rgb = linear_to_pq(max(color.rgb * REC709_TO_REC2020, vec3(0.0)) * effects.output_scale);
+	}
+
+	if (use_debanding) {
+		color.rgb = apply_debanding(color.rgb);
+	}
 
 	frag_color = color;
 }
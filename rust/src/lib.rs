@@ -2,9 +2,12 @@ mod post_effect;
 
 use godot::{
     classes::{
-        Engine, Image, RdSamplerState, RenderingServer,
+        Engine, Image, RdSamplerState, RdTextureFormat, RdTextureView, RenderingServer,
         image::Format,
-        rendering_device::{SamplerBorderColor, SamplerFilter, SamplerRepeatMode},
+        rendering_device::{
+            DataFormat, SamplerBorderColor, SamplerFilter, SamplerRepeatMode, TextureType,
+            TextureUsageBits,
+        },
     },
     prelude::*,
 };
@@ -16,10 +19,13 @@ pub struct GlobalRidsSingleton {
     pub glow_downsample_sampler: Rid,
     pub default_sampler: Rid,
     pub default_sampler_mipmaps: Rid,
+    pub nearest_sampler: Rid,
     pub default_texture_white: Rid,
     pub default_texture_black: Rid,
     pub default_texture_white_rs: Rid,
     pub default_texture_black_rs: Rid,
+    pub default_texture_3d_white: Rid,
+    pub default_texture_1d_white: Rid,
 }
 
 #[godot_api]
@@ -58,6 +64,16 @@ impl IObject for GlobalRidsSingleton {
             rd.sampler_create(&state)
         };
 
+        let nearest_sampler: Rid = {
+            let mut state = RdSamplerState::new_gd();
+            state.set_mag_filter(SamplerFilter::NEAREST);
+            state.set_min_filter(SamplerFilter::NEAREST);
+            state.set_max_lod(0.0);
+            state.set_repeat_u(SamplerRepeatMode::CLAMP_TO_EDGE);
+            state.set_repeat_v(SamplerRepeatMode::CLAMP_TO_EDGE);
+            rd.sampler_create(&state)
+        };
+
         let default_texture_white_rs: Rid = {
             let mut image = Image::create_empty(16, 16, false, Format::RGBA8).unwrap();
             image.fill(Color::WHITE);
@@ -74,15 +90,42 @@ impl IObject for GlobalRidsSingleton {
 
         let default_texture_black: Rid = rs.texture_get_rd_texture(default_texture_black_rs);
 
+        let default_texture_3d_white: Rid = {
+            let mut format = RdTextureFormat::new_gd();
+            format.set_format(DataFormat::R8G8B8A8_UNORM);
+            format.set_texture_type(TextureType::TYPE_3D);
+            format.set_width(1);
+            format.set_height(1);
+            format.set_depth(1);
+            format.set_usage_bits(TextureUsageBits::SAMPLING_BIT);
+            rd.texture_create_ex(&format, &RdTextureView::new_gd())
+                .data(&Array::from(&[PackedByteArray::from(&[255u8; 4])]))
+                .done()
+        };
+
+        let default_texture_1d_white: Rid = {
+            let mut format = RdTextureFormat::new_gd();
+            format.set_format(DataFormat::R8G8B8A8_UNORM);
+            format.set_texture_type(TextureType::TYPE_1D);
+            format.set_width(1);
+            format.set_usage_bits(TextureUsageBits::SAMPLING_BIT);
+            rd.texture_create_ex(&format, &RdTextureView::new_gd())
+                .data(&Array::from(&[PackedByteArray::from(&[255u8; 4])]))
+                .done()
+        };
+
         Self {
             base,
             glow_downsample_sampler,
             default_sampler,
             default_sampler_mipmaps,
+            nearest_sampler,
             default_texture_white,
             default_texture_black,
             default_texture_white_rs,
             default_texture_black_rs,
+            default_texture_3d_white,
+            default_texture_1d_white,
         }
     }
 }
@@ -97,6 +140,9 @@ impl Drop for GlobalRidsSingleton {
         rd.free_rid(self.glow_downsample_sampler);
         rd.free_rid(self.default_sampler);
         rd.free_rid(self.default_sampler_mipmaps);
+        rd.free_rid(self.nearest_sampler);
+        rd.free_rid(self.default_texture_3d_white);
+        rd.free_rid(self.default_texture_1d_white);
     }
 }
 
@@ -0,0 +1,366 @@
+use std::sync::{Arc, Mutex};
+
+use godot::{
+    classes::{Engine, RdUniform, UniformSetCacheRd, rendering_device::UniformType},
+    prelude::*,
+};
+use zerocopy::FromBytes;
+
+use crate::{GlobalRidsSingleton, post_effect::copy::Compute};
+
+const LUMINANCE_HISTOGRAM_SHADER_PATH: &str = "uid://c395ix8ralhaa";
+
+/// Keep in sync with `HISTOGRAM_BINS` in `luminance_histogram.glsl`.
+pub const HISTOGRAM_BINS: usize = 64;
+const HISTOGRAM_SIZE_BYTES: u32 = (HISTOGRAM_BINS * std::mem::size_of::<u32>()) as u32;
+
+/// Scene luminance that auto exposure maps to with zero compensation.
+const MIDDLE_GRAY: f32 = 0.18;
+
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum ExposureMode {
+    Manual,
+    Auto,
+    /// Derived from the `CameraAttributes` the viewport is rendered with.
+    Camera,
+}
+
+#[derive(Clone, Copy)]
+pub struct AutoExposureSettings {
+    pub min_ev: f32,
+    pub max_ev: f32,
+    /// Percentage of the darkest pixels ignored by the average.
+    pub low_percent: f32,
+    /// Percentage of the brightest pixels ignored by the average.
+    pub high_percent: f32,
+    pub compensation: f32,
+    /// Adaptation speed when the scene gets brighter, higher adapts faster.
+    pub speed_up: f32,
+    /// Adaptation speed when the scene gets darker, higher adapts faster.
+    pub speed_down: f32,
+}
+
+/// log2 luminance at the center of a histogram bin.
+fn bin_ev(bin: usize, min_ev: f32, max_ev: f32) -> f32 {
+    let t = (bin as f32 - 0.5) / (HISTOGRAM_BINS - 2) as f32;
+    (min_ev + t * (max_ev - min_ev)).min(max_ev)
+}
+
+/// Average log2 luminance of a histogram written by `luminance_histogram.glsl`,
+/// skipping the `low_percent` darkest and `high_percent` brightest pixels.
+/// Returns `None` for an empty histogram.
+pub fn histogram_average_ev(bins: &[u32], settings: &AutoExposureSettings) -> Option<f32> {
+    let total: f64 = bins.iter().map(|&b| f64::from(b)).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut low = total * f64::from(settings.low_percent.clamp(0.0, 100.0)) * 0.01;
+    let mut remaining = total * f64::from(settings.high_percent.clamp(0.0, 100.0)) * 0.01;
+    remaining = (total - low - remaining).max(0.0);
+
+    let mut sum = 0.0;
+    let mut weight = 0.0;
+    for (bin, &count) in bins.iter().enumerate() {
+        let mut count = f64::from(count);
+        let skip = count.min(low);
+        count -= skip;
+        low -= skip;
+        let take = count.min(remaining);
+        remaining -= take;
+        // Bin 0 holds black pixels, count it at the bottom of the range.
+        let ev = if bin == 0 {
+            settings.min_ev
+        } else {
+            bin_ev(bin, settings.min_ev, settings.max_ev)
+        };
+        sum += take * f64::from(ev);
+        weight += take;
+    }
+    if weight <= 0.0 {
+        return None;
+    }
+    Some(((sum / weight) as f32).clamp(settings.min_ev, settings.max_ev))
+}
+
+/// Moves `current_ev` towards `target_ev` with an exponential falloff.
+pub fn adapt_ev(
+    current_ev: f32,
+    target_ev: f32,
+    delta: f32,
+    settings: &AutoExposureSettings,
+) -> f32 {
+    let speed = if target_ev > current_ev {
+        settings.speed_up
+    } else {
+        settings.speed_down
+    };
+    current_ev + (target_ev - current_ev) * (1.0 - (-delta * speed.max(0.0)).exp())
+}
+
+pub fn exposure_from_ev(ev: f32, compensation: f32) -> f32 {
+    MIDDLE_GRAY * (compensation - ev).exp2()
+}
+
+#[derive(
+    Debug,
+    zerocopy::FromBytes,
+    zerocopy::IntoBytes,
+    zerocopy::Immutable,
+    zerocopy::KnownLayout,
+    Default,
+)]
+#[repr(C)]
+struct LuminanceHistogramPushConstants {
+    source_size_x: i32, // 04 - 04
+    source_size_y: i32, // 04 - 08
+    min_ev: f32,        // 04 - 12
+    inv_ev_range: f32,  // 04 - 16
+}
+
+/// Builds a log-luminance histogram of the color layer on the GPU and reads it
+/// back asynchronously, so the result lags the rendered frame by a few frames.
+pub struct LuminanceHistogram {
+    compute: Compute,
+    ubo: PackedArray<u8>,
+    uniforms: Array<Gd<RdUniform>>,
+    sampler: Rid,
+    histogram_buffer: Rid,
+    readback: Arc<Mutex<Readback>>,
+}
+
+#[derive(Default)]
+struct Readback {
+    pending: bool,
+    bins: Option<Vec<u32>>,
+}
+
+impl Drop for LuminanceHistogram {
+    fn drop(&mut self) {
+        if self.histogram_buffer.is_valid() {
+            self.compute.rd.free_rid(self.histogram_buffer);
+        }
+    }
+}
+
+impl LuminanceHistogram {
+    pub fn init() -> Self {
+        let mut compute = Compute::load_shader_file_path(LUMINANCE_HISTOGRAM_SHADER_PATH);
+        let histogram_buffer = compute.rd.storage_buffer_create(HISTOGRAM_SIZE_BYTES);
+
+        let ubo_bytes: [u8; std::mem::size_of::<LuminanceHistogramPushConstants>()] =
+            zerocopy::transmute!(LuminanceHistogramPushConstants::default());
+        let ubo = PackedArray::<u8>::from(&ubo_bytes);
+
+        let mut uniforms = Array::new();
+        let mut uniform_src_tex = RdUniform::new_gd();
+        uniform_src_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_src_tex.set_binding(0);
+        uniforms.push(&uniform_src_tex);
+        let mut uniform_histogram = RdUniform::new_gd();
+        uniform_histogram.set_uniform_type(UniformType::STORAGE_BUFFER);
+        uniform_histogram.set_binding(1);
+        uniform_histogram.add_id(histogram_buffer);
+        uniforms.push(&uniform_histogram);
+
+        let sampler = Engine::singleton()
+            .get_singleton(&GlobalRidsSingleton::class_name().to_string_name())
+            .unwrap()
+            .cast::<GlobalRidsSingleton>()
+            .bind()
+            .default_sampler;
+
+        Self {
+            compute,
+            ubo,
+            uniforms,
+            sampler,
+            histogram_buffer,
+            readback: Arc::new(Mutex::new(Readback::default())),
+        }
+    }
+
+    /// Returns the most recent histogram that finished reading back, if any.
+    pub fn take_bins(&mut self) -> Option<Vec<u32>> {
+        self.readback.lock().unwrap().bins.take()
+    }
+
+    pub fn exec(&mut self, source_rd_texture: Rid, size: Vector2i, min_ev: f32, max_ev: f32) {
+        // Only one histogram is in flight, the buffer is reused once it has been read.
+        if self.readback.lock().unwrap().pending {
+            return;
+        }
+        let rd = &mut self.compute.rd;
+        rd.buffer_clear(self.histogram_buffer, 0, HISTOGRAM_SIZE_BYTES);
+
+        // UBO.
+        let ubo = self.ubo.as_mut_slice();
+        let ubo_mut = LuminanceHistogramPushConstants::mut_from_bytes(ubo).unwrap();
+        ubo_mut.source_size_x = size.x;
+        ubo_mut.source_size_y = size.y;
+        ubo_mut.min_ev = min_ev;
+        ubo_mut.inv_ev_range = 1.0 / (max_ev - min_ev).max(1e-3);
+
+        let mut uniform_src_tex = self.uniforms.get(0).unwrap();
+        uniform_src_tex.clear_ids();
+        uniform_src_tex.add_id(self.sampler);
+        uniform_src_tex.add_id(source_rd_texture);
+        let uniform_set = UniformSetCacheRd::get_cache(self.compute.shader, 0, &self.uniforms);
+
+        let compute_list = rd.compute_list_begin();
+        rd.compute_list_bind_compute_pipeline(compute_list, self.compute.pipeline);
+        rd.compute_list_bind_uniform_set(compute_list, uniform_set, 0);
+        rd.compute_list_set_push_constant(
+            compute_list,
+            &self.ubo,
+            self.ubo.len().try_into().unwrap(),
+        );
+        rd.compute_list_dispatch(
+            compute_list,
+            ((size.x + 15) / 16).try_into().unwrap(),
+            ((size.y + 15) / 16).try_into().unwrap(),
+            1,
+        );
+        rd.compute_list_end();
+
+        self.readback.lock().unwrap().pending = true;
+        let readback = self.readback.clone();
+        let callback = Callable::from_sync_fn("luminance_histogram_readback", move |args| {
+            let data = args[0].to::<PackedByteArray>();
+            let bins = data
+                .as_slice()
+                .chunks_exact(std::mem::size_of::<u32>())
+                .map(|b| u32::from_ne_bytes(b.try_into().unwrap()))
+                .collect::<Vec<_>>();
+            let mut readback = readback.lock().unwrap();
+            readback.pending = false;
+            if bins.len() == HISTOGRAM_BINS {
+                readback.bins = Some(bins);
+            }
+            Ok(Variant::nil())
+        });
+        rd.buffer_get_data_async(self.histogram_buffer, &callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: AutoExposureSettings = AutoExposureSettings {
+        min_ev: -8.0,
+        max_ev: 8.0,
+        low_percent: 0.0,
+        high_percent: 0.0,
+        compensation: 0.0,
+        speed_up: 3.0,
+        speed_down: 1.0,
+    };
+
+    /// `count` pixels in each of `filled` bins.
+    fn histogram(filled: &[(usize, u32)]) -> Vec<u32> {
+        let mut bins = vec![0; HISTOGRAM_BINS];
+        for &(bin, count) in filled {
+            bins[bin] = count;
+        }
+        bins
+    }
+
+    #[test]
+    fn empty_histogram() {
+        assert_eq!(histogram_average_ev(&[], &SETTINGS), None);
+        assert_eq!(histogram_average_ev(&histogram(&[]), &SETTINGS), None);
+    }
+
+    #[test]
+    fn all_pixels_rejected() {
+        let bins = histogram(&[(10, 100), (50, 100)]);
+        let settings = AutoExposureSettings {
+            low_percent: 60.0,
+            high_percent: 40.0,
+            ..SETTINGS
+        };
+        assert_eq!(histogram_average_ev(&bins, &settings), None);
+        let settings = AutoExposureSettings {
+            low_percent: 100.0,
+            ..SETTINGS
+        };
+        assert_eq!(histogram_average_ev(&bins, &settings), None);
+    }
+
+    #[test]
+    fn percent_clipping() {
+        let bins = histogram(&[(10, 100), (50, 100)]);
+        let dark = bin_ev(10, SETTINGS.min_ev, SETTINGS.max_ev);
+        let bright = bin_ev(50, SETTINGS.min_ev, SETTINGS.max_ev);
+
+        let average = histogram_average_ev(&bins, &SETTINGS).unwrap();
+        assert!((average - (dark + bright) / 2.0).abs() < 1e-5, "{average}");
+
+        let low = AutoExposureSettings {
+            low_percent: 50.0,
+            ..SETTINGS
+        };
+        let average = histogram_average_ev(&bins, &low).unwrap();
+        assert!((average - bright).abs() < 1e-5, "{average}");
+
+        let high = AutoExposureSettings {
+            high_percent: 50.0,
+            ..SETTINGS
+        };
+        let average = histogram_average_ev(&bins, &high).unwrap();
+        assert!((average - dark).abs() < 1e-5, "{average}");
+
+        // Clipping a quarter of the pixels splits a bin.
+        let quarter = AutoExposureSettings {
+            low_percent: 25.0,
+            ..SETTINGS
+        };
+        let average = histogram_average_ev(&bins, &quarter).unwrap();
+        let expected = (dark + 2.0 * bright) / 3.0;
+        assert!((average - expected).abs() < 1e-5, "{average}");
+    }
+
+    #[test]
+    fn black_pixels_count_at_min_ev() {
+        let bins = histogram(&[(0, 100)]);
+        assert_eq!(
+            histogram_average_ev(&bins, &SETTINGS),
+            Some(SETTINGS.min_ev)
+        );
+    }
+
+    #[test]
+    fn adaptation_direction_and_speed() {
+        let delta = 0.1;
+        let up = adapt_ev(0.0, 4.0, delta, &SETTINGS);
+        let down = adapt_ev(0.0, -4.0, delta, &SETTINGS);
+        assert!(up > 0.0 && up < 4.0, "{up}");
+        assert!(down < 0.0 && down > -4.0, "{down}");
+        // `speed_up` is faster than `speed_down`.
+        assert!(up > -down, "{up} {down}");
+        let expected = 4.0 * (1.0 - (-delta * SETTINGS.speed_up).exp());
+        assert!((up - expected).abs() < 1e-5, "{up}");
+
+        assert_eq!(adapt_ev(1.0, 4.0, 0.0, &SETTINGS), 1.0);
+        assert_eq!(adapt_ev(2.0, 2.0, delta, &SETTINGS), 2.0);
+        assert!((adapt_ev(0.0, 4.0, 100.0, &SETTINGS) - 4.0).abs() < 1e-4);
+        let frozen = AutoExposureSettings {
+            speed_down: 0.0,
+            ..SETTINGS
+        };
+        assert_eq!(adapt_ev(1.0, -4.0, delta, &frozen), 1.0);
+    }
+
+    #[test]
+    fn exposure_maps_average_to_middle_gray() {
+        for ev in [-6.0, -1.5, 0.0, 2.0, 7.0] {
+            let exposure = exposure_from_ev(ev, 0.0);
+            assert!(
+                (exposure * f32::exp2(ev) - MIDDLE_GRAY).abs() < 1e-5,
+                "{ev}"
+            );
+        }
+        assert!((exposure_from_ev(1.0, 1.0) - 2.0 * exposure_from_ev(1.0, 0.0)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,74 @@
+//! Photographic exposure math, following "Moving Frostbite to Physically Based Rendering".
+
+/// EV100 of Godot's default `CameraAttributesPhysical` (f/16, 1/100 s, ISO 100).
+pub const DEFAULT_EV100: f32 = 14.643_856;
+
+/// Exposure value at ISO 100.
+///
+/// `shutter_speed` uses Godot's convention: the exposure time is `1 / shutter_speed` seconds.
+pub fn ev100(aperture: f32, shutter_speed: f32, sensitivity: f32) -> f32 {
+    (aperture * aperture * shutter_speed * 100.0 / sensitivity).log2()
+}
+
+/// Exposure relative to Godot's default physical camera, for scenes that don't use
+/// physical light units. The default camera maps to 1.0 and every stop doubles or halves it.
+pub fn relative_exposure(ev100: f32) -> f32 {
+    (DEFAULT_EV100 - ev100).exp2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::post_effect::test_util::assert_close;
+
+    #[test]
+    fn ev100_matches_exposure_table() {
+        // (aperture, 1 / seconds, EV100) from the standard exposure value table.
+        let table = [
+            (1.0, 1.0, 0.0),
+            (1.4, 1.0, 1.0),
+            (2.0, 1.0, 2.0),
+            (2.0, 2.0, 3.0),
+            (4.0, 15.0, 8.0),
+            (5.6, 30.0, 10.0),
+            (8.0, 60.0, 12.0),
+            (11.0, 125.0, 14.0),
+            (16.0, 125.0, 15.0),
+            (22.0, 250.0, 17.0),
+        ];
+        for (aperture, shutter_speed, ev) in table {
+            // Marked f-numbers and shutter speeds are rounded, so allow a fraction of a stop.
+            assert_close(ev100(aperture, shutter_speed, 100.0), ev, 0.15);
+        }
+        assert_close(ev100(16.0, 100.0, 100.0), DEFAULT_EV100, 1e-4);
+    }
+
+    #[test]
+    fn sensitivity_shifts_by_stops() {
+        assert_close(ev100(8.0, 60.0, 200.0), ev100(8.0, 60.0, 100.0) - 1.0, 1e-5);
+        assert_close(ev100(8.0, 60.0, 50.0), ev100(8.0, 60.0, 100.0) + 1.0, 1e-5);
+        assert_close(
+            ev100(8.0, 60.0, 1600.0),
+            ev100(8.0, 60.0, 100.0) - 4.0,
+            1e-5,
+        );
+    }
+
+    #[test]
+    fn relative_exposure_ratios() {
+        // (aperture, 1 / seconds, ISO, exposure relative to the default f/16, 1/100 s, ISO 100).
+        let table = [
+            (16.0, 100.0, 100.0, 1.0),
+            (8.0, 100.0, 100.0, 4.0),
+            (16.0, 50.0, 100.0, 2.0),
+            (16.0, 400.0, 100.0, 0.25),
+            (16.0, 100.0, 400.0, 4.0),
+            (16.0 * std::f32::consts::SQRT_2, 100.0, 100.0, 0.5),
+            (4.0, 25.0, 50.0, 32.0),
+        ];
+        for (aperture, shutter_speed, sensitivity, exposure) in table {
+            let ev = ev100(aperture, shutter_speed, sensitivity);
+            assert_close(relative_exposure(ev), exposure, exposure * 1e-4);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use godot::{
+    classes::{
+        Engine, RdPipelineSpecializationConstant, RdUniform, UniformSetCacheRd,
+        rendering_device::UniformType,
+    },
+    prelude::*,
+};
+use zerocopy::FromBytes;
+
+use crate::{GlobalRidsSingleton, post_effect::copy::Raster};
+
+const CAS_SHADER_PATH: &str = "uid://bgle6dimwuy6r";
+
+#[derive(
+    Debug,
+    zerocopy::FromBytes,
+    zerocopy::IntoBytes,
+    zerocopy::Immutable,
+    zerocopy::KnownLayout,
+    Default,
+)]
+#[repr(C)]
+struct CasPushConstants {
+    peak: f32,      // 04 - 04
+    pad0: f32,      // 04 - 08
+    pad1: [f32; 2], // 08 - 16
+}
+
+/// Weight of the neighbors at full local contrast, as in AMD's CAS.
+pub fn cas_peak(sharpness: f32) -> f32 {
+    -1.0 / (8.0 + (5.0 - 8.0) * sharpness.clamp(0.0, 1.0))
+}
+
+/// Contrast adaptive sharpening of the tonemapped image.
+pub struct Cas {
+    renderer: Raster,
+    scs: Array<Gd<RdPipelineSpecializationConstant>>,
+    ubo: PackedArray<u8>,
+    uniforms: Array<Gd<RdUniform>>,
+    sampler: Rid,
+}
+
+impl Cas {
+    pub fn init() -> Self {
+        let ubo_bytes: [u8; std::mem::size_of::<CasPushConstants>()] =
+            zerocopy::transmute!(CasPushConstants::default());
+        let ubo = PackedArray::<u8>::from(&ubo_bytes);
+
+        let mut uniforms = Array::new();
+        let mut uniform_src_tex = RdUniform::new_gd();
+        uniform_src_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_src_tex.set_binding(0);
+        uniforms.push(&uniform_src_tex);
+
+        let sampler = Engine::singleton()
+            .get_singleton(&GlobalRidsSingleton::class_name().to_string_name())
+            .unwrap()
+            .cast::<GlobalRidsSingleton>()
+            .bind()
+            .default_sampler;
+
+        Self {
+            renderer: Raster::load_shader_file_path(CAS_SHADER_PATH),
+            scs: Array::new(),
+            ubo,
+            uniforms,
+            sampler,
+        }
+    }
+
+    pub fn exec(&mut self, source_rd_texture: Rid, dest_texture: Rid, sharpness: f32) {
+        // Pipeline.
+        self.renderer.setup_pipeline_texure(dest_texture, &self.scs);
+        // UBO.
+        let ubo = self.ubo.as_mut_slice();
+        let ubo_mut = CasPushConstants::mut_from_bytes(ubo).unwrap();
+        ubo_mut.peak = cas_peak(sharpness);
+
+        let mut uniform_src_tex = self.uniforms.get(0).unwrap();
+        uniform_src_tex.clear_ids();
+        uniform_src_tex.add_id(self.sampler);
+        uniform_src_tex.add_id(source_rd_texture);
+        let uniform_set0 = UniformSetCacheRd::get_cache(self.renderer.shader, 0, &self.uniforms);
+
+        let draw_list = self.renderer.rd.draw_list_begin(self.renderer.framebuffer);
+        self.renderer
+            .rd
+            .draw_list_bind_render_pipeline(draw_list, self.renderer.pipeline);
+        self.renderer.rd.draw_list_set_push_constant(
+            draw_list,
+            &self.ubo,
+            self.ubo.len().try_into().unwrap(),
+        );
+        self.renderer
+            .rd
+            .draw_list_bind_uniform_set(draw_list, uniform_set0, 0);
+        self.renderer
+            .rd
+            .draw_list_draw_ex(draw_list, false, 1)
+            .procedural_vertex_count(3)
+            .done();
+        self.renderer.rd.draw_list_end();
+    }
+}
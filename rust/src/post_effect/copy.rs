@@ -13,16 +13,47 @@ use godot::{
 };
 use zerocopy::FromBytes;
 
-use crate::GlobalRidsSingleton;
+use crate::{
+    GlobalRidsSingleton,
+    post_effect::{
+        cas::cas_peak,
+        grading::{Cdl, Mat3},
+        hdr::{display_peak, pq_scale, scrgb_scale},
+        karis::suppression_strength,
+    },
+};
 
 const TEX_COPY_SHADER_PATH: &str = "uid://bky734u2m1ik4";
 const DOWNSAMPLER_SHADER_PATH: &str = "uid://dn7kvwu3pc8ht";
 const UPSAMPLE_SHADER_PATH: &str = "uid://d20ptrfi77euk";
 const TONEMAPPER_SHADER_PATH: &str = "uid://dch7mum06agob";
+const STREAK_SHADER_PATH: &str = "uid://c3qakrm7xsaum";
 
 const SC_TONEMAP_TYPE_INDEX: u8 = 2;
-const SC_GLOW_MODE_INDEX: u8 = 9;
-const SC_MAX_INDEX: u8 = 11;
+const SC_GLOW_MODE_INDEX: u8 = 12;
+const SC_USE_COLOR_LUT_INDEX: u8 = 17;
+const SC_TONEMAP_MODE_INDEX: u8 = 18;
+const SC_USE_BCS_INDEX: u8 = 21;
+const SC_USE_COLOR_CORRECTION_INDEX: u8 = 22;
+const SC_USE_1D_COLOR_CORRECTION_INDEX: u8 = 23;
+const SC_USE_GRADING_INDEX: u8 = 24;
+const SC_GRADING_ACESCC_INDEX: u8 = 25;
+const SC_GRADING_AFTER_TONEMAP_INDEX: u8 = 26;
+const SC_USE_LENS_DIRT_INDEX: u8 = 27;
+const SC_USE_STREAK_INDEX: u8 = 28;
+const SC_USE_LENS_FLARE_INDEX: u8 = 29;
+const SC_USE_VIGNETTE_INDEX: u8 = 30;
+const SC_VIGNETTE_AFTER_TONEMAP_INDEX: u8 = 31;
+const SC_USE_GRAIN_INDEX: u8 = 32;
+const SC_USE_GRAIN_TEXTURE_INDEX: u8 = 33;
+const SC_USE_DEBANDING_INDEX: u8 = 34;
+const SC_USE_CHROMATIC_ABERRATION_INDEX: u8 = 35;
+const SC_USE_SPECTRAL_LUT_INDEX: u8 = 36;
+const SC_USE_LENS_DISTORTION_INDEX: u8 = 37;
+const SC_USE_FXAA_SHARPEN_INDEX: u8 = 38;
+const SC_OUTPUT_SCRGB_INDEX: u8 = 39;
+const SC_OUTPUT_HDR10_INDEX: u8 = 40;
+const SC_MAX_INDEX: u8 = 40;
 
 pub struct Raster {
     pub rd: Gd<RenderingDevice>,
@@ -163,6 +194,38 @@ impl Raster {
     }
 }
 
+pub struct Compute {
+    pub rd: Gd<RenderingDevice>,
+    pub shader: Rid,
+    pub pipeline: Rid,
+}
+
+impl Drop for Compute {
+    fn drop(&mut self) {
+        if self.shader.is_valid() {
+            self.rd.free_rid(self.shader);
+        }
+    }
+}
+
+impl Compute {
+    pub fn load_shader_file(shader_file: &Gd<RdShaderFile>) -> Self {
+        let mut rd = RenderingServer::singleton().get_rendering_device().unwrap();
+        let spirv = shader_file.get_spirv().unwrap();
+        let shader = rd.shader_create_from_spirv(&spirv);
+        let pipeline = rd.compute_pipeline_create(shader);
+        Self {
+            rd,
+            shader,
+            pipeline,
+        }
+    }
+
+    pub fn load_shader_file_path(path: impl AsArg<GString>) -> Self {
+        Self::load_shader_file(&ResourceLoader::singleton().load(path).unwrap().cast())
+    }
+}
+
 pub struct TexCopy {
     renderer: Raster,
     scs: Array<Gd<RdPipelineSpecializationConstant>>,
@@ -226,10 +289,10 @@ impl TexCopy {
 )]
 #[repr(C)]
 struct BlurDownsamplePushConstants {
-    source_pixel_size_x: f32, // 04 - 12
-    source_pixel_size_y: f32, // 04 - 16
-    pad1: f32,
-    pad2: f32,
+    source_pixel_size_x: f32, // 04 - 04
+    source_pixel_size_y: f32, // 04 - 08
+    firefly_suppression: f32, // 04 - 12
+    glow_knee: f32,           // 04 - 16
     // Glow.
     glow_strength: f32,      // 04 - 20
     glow_bloom: f32,         // 04 - 24
@@ -239,7 +302,7 @@ struct BlurDownsamplePushConstants {
     glow_exposure: f32,        // 04 - 36
     glow_luminance_cap: f32,   // 04 - 40
     luminance_multiplier: f32, // 04 - 44
-    pad: f32,                  // 04 - 48
+    glow_threshold: f32,       // 04 - 48
 }
 
 pub struct BlurDownsample {
@@ -255,6 +318,9 @@ impl BlurDownsample {
         let mut sc = RdPipelineSpecializationConstant::new_gd();
         sc.set_constant_id(0);
         sc.set_value(&false.to_variant());
+        let mut sc_soft_knee = RdPipelineSpecializationConstant::new_gd();
+        sc_soft_knee.set_constant_id(1);
+        sc_soft_knee.set_value(&false.to_variant());
         let ubo_bytes: [u8; std::mem::size_of::<BlurDownsamplePushConstants>()] =
             zerocopy::transmute!(BlurDownsamplePushConstants::default());
         let ubo = PackedArray::<u8>::from(&ubo_bytes);
@@ -273,7 +339,7 @@ impl BlurDownsample {
 
         Self {
             renderer: Raster::load_shader_file_path(DOWNSAMPLER_SHADER_PATH),
-            scs: Array::from(&[sc]),
+            scs: Array::from(&[sc, sc_soft_knee]),
             ubo,
             uniforms,
             sampler,
@@ -293,9 +359,13 @@ impl BlurDownsample {
         bloom: f32,
         hdr_bleed_threshold: f32,
         hdr_bleed_scale: f32,
+        firefly_suppression: f32,
+        threshold: GlowThreshold,
     ) {
         // Specialization constant.
         self.scs.get(0).unwrap().set_value(&first_pass.to_variant());
+        let soft_knee = matches!(threshold, GlowThreshold::SoftKnee { .. });
+        self.scs.get(1).unwrap().set_value(&soft_knee.to_variant());
         // Pipeline.
         self.renderer.setup_pipeline_texure(dest_texture, &self.scs);
         // UBO.
@@ -310,6 +380,11 @@ impl BlurDownsample {
         ubo_mut.glow_exposure = exposure;
         ubo_mut.glow_luminance_cap = luminance_cap;
         ubo_mut.luminance_multiplier = luminance_multiplier;
+        ubo_mut.firefly_suppression = suppression_strength(firefly_suppression);
+        if let GlowThreshold::SoftKnee { threshold, knee } = threshold {
+            ubo_mut.glow_threshold = threshold;
+            ubo_mut.glow_knee = knee;
+        }
         let mut uniform_src_tex = self.uniforms.get(0).unwrap();
         uniform_src_tex.clear_ids();
         uniform_src_tex.add_id(self.sampler);
@@ -355,6 +430,11 @@ struct BlurUpsamplePushConstants {
     glow_strength: f32, // 04 - 24
     pad1: f32,          // 04 - 28
     pad2: f32,          // 04 - 32
+
+    glow_level_tint: [f32; 3],    // 12 - 44
+    pad3: f32,                    // 04 - 48
+    glow_strength_tint: [f32; 3], // 12 - 60
+    pad4: f32,                    // 04 - 64
 }
 
 pub struct BlurUpsample {
@@ -411,6 +491,8 @@ impl BlurUpsample {
         dest_size: Vector2i,
         level: f32,
         base_strength: f32,
+        level_tint: Color,
+        base_tint: Color,
     ) {
         // Specialization constant.
         self.scs
@@ -428,6 +510,8 @@ impl BlurUpsample {
         ubo_mut.dest_pixel_size_y = 1.0 / dest_size.y as f32;
         ubo_mut.glow_level = level * 0.5;
         ubo_mut.glow_strength = base_strength;
+        ubo_mut.glow_level_tint = [level_tint.r, level_tint.g, level_tint.b];
+        ubo_mut.glow_strength_tint = [base_tint.r, base_tint.g, base_tint.b];
 
         let mut uniform_src_tex = self.uniforms_src.get(0).unwrap();
         uniform_src_tex.clear_ids();
@@ -468,6 +552,127 @@ impl BlurUpsample {
     }
 }
 
+/// Number of streak passes, each one spreads four times further than the previous.
+pub const STREAK_PASSES: u32 = 4;
+
+#[derive(
+    Debug,
+    zerocopy::FromBytes,
+    zerocopy::IntoBytes,
+    zerocopy::Immutable,
+    zerocopy::KnownLayout,
+    Default,
+)]
+#[repr(C)]
+struct StreakPushConstants {
+    source_pixel_size_x: f32, // 04 - 04
+    source_pixel_size_y: f32, // 04 - 08
+    step_x: f32,              // 04 - 12
+    step_y: f32,              // 04 - 16
+
+    tap_attenuation: f32, // 04 - 20
+    pad1: f32,            // 04 - 24
+    pad2: f32,            // 04 - 28
+    pad3: f32,            // 04 - 32
+
+    tint: [f32; 3], // 12 - 44
+    pad4: f32,      // 04 - 48
+}
+
+/// Anamorphic streaks, a separable filter along one direction run over the
+/// thresholded first glow downsample.
+pub struct Streak {
+    renderer: Raster,
+    scs: Array<Gd<RdPipelineSpecializationConstant>>,
+    ubo: PackedArray<u8>,
+    uniforms_src: Array<Gd<RdUniform>>,
+    sampler: Rid,
+}
+
+impl Streak {
+    pub fn init() -> Self {
+        let ubo_bytes: [u8; std::mem::size_of::<StreakPushConstants>()] =
+            zerocopy::transmute!(StreakPushConstants::default());
+        let ubo = PackedArray::<u8>::from(&ubo_bytes);
+
+        let mut uniforms_src = Array::new();
+        let mut uniform_src_tex = RdUniform::new_gd();
+        uniform_src_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_src_tex.set_binding(0);
+        uniforms_src.push(&uniform_src_tex);
+
+        let sampler = Engine::singleton()
+            .get_singleton(&GlobalRidsSingleton::class_name().to_string_name())
+            .unwrap()
+            .cast::<GlobalRidsSingleton>()
+            .bind()
+            .default_sampler;
+        Self {
+            renderer: Raster::load_shader_file_path(STREAK_SHADER_PATH),
+            scs: Array::new(),
+            ubo,
+            uniforms_src,
+            sampler,
+        }
+    }
+
+    /// Runs pass `pass` of `STREAK_PASSES`. `direction` is normalized, `length` is the
+    /// attenuation per source pixel, and `tint` is applied by the last pass only.
+    pub fn exec(
+        &mut self,
+        source_rd_texture: Rid,
+        dest_texture: Rid,
+        size: Vector2i,
+        pass: u32,
+        direction: Vector2,
+        length: f32,
+        tint: Color,
+    ) {
+        // Pipeline.
+        self.renderer.setup_pipeline_texure(dest_texture, &self.scs);
+        // UBO.
+        let spacing = 4u32.pow(pass) as f32;
+        let ubo = self.ubo.as_mut_slice();
+        let ubo_mut = StreakPushConstants::mut_from_bytes(ubo).unwrap();
+        ubo_mut.source_pixel_size_x = 1.0 / size.x as f32;
+        ubo_mut.source_pixel_size_y = 1.0 / size.y as f32;
+        ubo_mut.step_x = direction.x * spacing;
+        ubo_mut.step_y = direction.y * spacing;
+        ubo_mut.tap_attenuation = length.clamp(0.0, 0.999).powf(spacing);
+        ubo_mut.tint = if pass + 1 == STREAK_PASSES {
+            [tint.r, tint.g, tint.b]
+        } else {
+            [1.0; 3]
+        };
+
+        let mut uniform_src_tex = self.uniforms_src.get(0).unwrap();
+        uniform_src_tex.clear_ids();
+        uniform_src_tex.add_id(self.sampler);
+        uniform_src_tex.add_id(source_rd_texture);
+        let uniform_set0 =
+            UniformSetCacheRd::get_cache(self.renderer.shader, 0, &self.uniforms_src);
+
+        let draw_list = self.renderer.rd.draw_list_begin(self.renderer.framebuffer);
+        self.renderer
+            .rd
+            .draw_list_bind_render_pipeline(draw_list, self.renderer.pipeline);
+        self.renderer.rd.draw_list_set_push_constant(
+            draw_list,
+            &self.ubo,
+            self.ubo.len().try_into().unwrap(),
+        );
+        self.renderer
+            .rd
+            .draw_list_bind_uniform_set(draw_list, uniform_set0, 0);
+        self.renderer
+            .rd
+            .draw_list_draw_ex(draw_list, false, 1)
+            .procedural_vertex_count(3)
+            .done();
+        self.renderer.rd.draw_list_end();
+    }
+}
+
 #[derive(
     Debug,
     zerocopy::FromBytes,
@@ -478,18 +683,93 @@ impl BlurUpsample {
 )]
 #[repr(C)]
 struct ToneMapperPushConstants {
-    dest_pixel_size_x: f32, // 04 - 20
-    dest_pixel_size_y: f32, // 04 - 24
-    glow_pixel_size_x: f32, // 04 - 28
-    glow_pixel_size_y: f32, // 04 - 32
+    dest_pixel_size_x: f32, // 04 - 04
+    dest_pixel_size_y: f32, // 04 - 08
+    glow_pixel_size_x: f32, // 04 - 12
+    glow_pixel_size_y: f32, // 04 - 16
+
+    glow_intensity: f32,    // 04 - 20
+    glow_map_strength: f32, // 04 - 24
+    exposure: f32,          // 04 - 28
+    white: f32,             // 04 - 32
+
+    color_lut_domain_min: [f32; 3],   // 12 - 44
+    tonemap_mode_blend: f32,          // 04 - 48
+    color_lut_domain_scale: [f32; 3], // 12 - 60
+    lens_dirt_intensity: f32,         // 04 - 64
+
+    tonemap_params: [[f32; 4]; 3], // 48 - 112
+
+    bcs: [f32; 3], // 12 - 124
+    frame: u32,    // 04 - 128
+}
+
+/// std140 layout of the `Grading` uniform buffer.
+#[derive(
+    Debug,
+    zerocopy::FromBytes,
+    zerocopy::IntoBytes,
+    zerocopy::Immutable,
+    zerocopy::KnownLayout,
+    Default,
+)]
+#[repr(C)]
+struct ToneMapperGradingUniforms {
+    color_matrix: [[f32; 4]; 3], // 48 - 48
+
+    lift: [f32; 4],      // 16 - 64
+    inv_gamma: [f32; 4], // 16 - 80
+    gain: [f32; 4],      // 16 - 96
+
+    cdl_slope: [f32; 4],  // 16 - 112
+    cdl_offset: [f32; 4], // 16 - 128
+    cdl_power: [f32; 4],  // 16 - 144
+}
+
+/// std140 layout of the `Effects` uniform buffer, screen effects without room in the push
+/// constants.
+#[derive(
+    Debug,
+    zerocopy::FromBytes,
+    zerocopy::IntoBytes,
+    zerocopy::Immutable,
+    zerocopy::KnownLayout,
+    Default,
+)]
+#[repr(C)]
+struct ToneMapperEffectsUniforms {
+    vignette_color: [f32; 4], // 16 - 16
+
+    vignette_center: [f32; 2], // 08 - 24
+    vignette_scale: f32,       // 04 - 28
+    vignette_smoothness: f32,  // 04 - 32
+    vignette_roundness: f32,   // 04 - 36
+    grain_intensity: f32,      // 04 - 40
+    grain_size: f32,           // 04 - 44
+    grain_response: f32,       // 04 - 48
+
+    debanding_scale: f32,              // 04 - 52
+    chromatic_aberration_amount: f32,  // 04 - 56
+    chromatic_aberration_samples: i32, // 04 - 60
+    lens_distortion_intensity: f32,    // 04 - 64
+
+    lens_distortion_scale: f32,   // 04 - 68
+    fxaa_sharpen_peak: f32,       // 04 - 72
+    fxaa_edge_threshold_min: f32, // 04 - 76
+    fxaa_edge_threshold_max: f32, // 04 - 80
+
+    fxaa_subpixel_quality: f32, // 04 - 84
+    fxaa_iterations: i32,       // 04 - 88
+    pad0: [f32; 2],             // 08 - 96
+
+    fxaa_steps: [[f32; 4]; 3], // 48 - 144
 
-    glow_intensity: f32,    // 04 - 36
-    glow_map_strength: f32, // 04 - 40
-    exposure: f32,          // 04 - 44
-    white: f32,             // 04 - 48
+    output_peak: f32,  // 04 - 148
+    output_scale: f32, // 04 - 152
+    pad1: [f32; 2],    // 08 - 160
 }
 
-#[derive(GodotConvert, Var, Export, Clone, Copy)]
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
 #[godot(via = i64)]
 pub enum ToneMapperType {
     Linear,
@@ -499,6 +779,154 @@ pub enum ToneMapperType {
     Agx,
     Gt,
     Lottes,
+    PbrNeutral,
+    Hable,
+    Custom,
+}
+
+/// How the tonemapper is applied to a color.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum ToneMapMode {
+    PerChannel,
+    /// Tonemaps the max RGB component and scales the color by the same ratio.
+    MaxRgb,
+    /// Tonemaps the luminance, desaturating colors that would leave the gamut.
+    Luminance,
+}
+
+/// Color space lift/gamma/gain and the CDL are applied in.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum GradingSpace {
+    Linear,
+    /// Log encoded ACES AP1, the usual space for CDLs from grading tools.
+    AcesCc,
+}
+
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum AgxLook {
+    None,
+    Punchy,
+    Golden,
+    /// Uses the `agx_look_*` overrides.
+    Custom,
+}
+
+/// ASC CDL style look applied after the AgX sigmoid.
+#[derive(Clone, Copy)]
+pub struct AgxLookParams {
+    pub offset: [f32; 3],
+    pub slope: [f32; 3],
+    pub power: [f32; 3],
+    pub saturation: f32,
+}
+
+impl AgxLook {
+    /// Blender's look presets, `None` for `AgxLook::Custom`.
+    pub fn preset(self) -> Option<AgxLookParams> {
+        let (slope, power, saturation) = match self {
+            AgxLook::None => ([1.0; 3], [1.0; 3], 1.0),
+            AgxLook::Punchy => ([1.0; 3], [1.35; 3], 1.4),
+            AgxLook::Golden => ([1.0, 0.9, 0.5], [0.8; 3], 0.8),
+            AgxLook::Custom => return None,
+        };
+        Some(AgxLookParams {
+            offset: [0.0; 3],
+            slope,
+            power,
+            saturation,
+        })
+    }
+}
+
+/// How the first glow downsample selects the pixels that glow.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum GlowThresholdMode {
+    /// Godot's `glow_hdr_bleed_threshold` and `glow_hdr_bleed_scale`.
+    Smoothstep,
+    /// Soft-knee curve with the threshold and knee width in EV of the exposed color.
+    SoftKneeEv,
+}
+
+/// FXAA 3.11 quality presets. 1x trade steps for larger jumps and are the fastest, 2x are
+/// the default range and 39 searches the furthest with the smallest steps.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum FxaaQuality {
+    Preset10 = 10,
+    Preset11 = 11,
+    Preset12 = 12,
+    Preset13 = 13,
+    Preset14 = 14,
+    Preset15 = 15,
+    Preset20 = 20,
+    Preset21 = 21,
+    Preset22 = 22,
+    Preset23 = 23,
+    Preset24 = 24,
+    Preset25 = 25,
+    Preset26 = 26,
+    Preset27 = 27,
+    Preset28 = 28,
+    Preset29 = 29,
+    Preset39 = 39,
+}
+
+impl FxaaQuality {
+    /// Length of each edge search step in pixels, `FXAA_QUALITY__P0` to `P11`.
+    pub fn steps(self) -> &'static [f32] {
+        match self {
+            Self::Preset10 => &[1.5, 3.0, 12.0],
+            Self::Preset11 => &[1.0, 1.5, 3.0, 12.0],
+            Self::Preset12 => &[1.0, 1.5, 2.0, 4.0, 12.0],
+            Self::Preset13 => &[1.0, 1.5, 2.0, 2.0, 4.0, 12.0],
+            Self::Preset14 => &[1.0, 1.5, 2.0, 2.0, 2.0, 4.0, 12.0],
+            Self::Preset15 => &[1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 12.0],
+            Self::Preset20 => &[1.5, 2.0, 8.0],
+            Self::Preset21 => &[1.0, 1.5, 2.0, 8.0],
+            Self::Preset22 => &[1.0, 1.5, 2.0, 2.0, 8.0],
+            Self::Preset23 => &[1.0, 1.5, 2.0, 2.0, 2.0, 8.0],
+            Self::Preset24 => &[1.0, 1.5, 2.0, 2.0, 2.0, 3.0, 8.0],
+            Self::Preset25 => &[1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0],
+            Self::Preset26 => &[1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0],
+            Self::Preset27 => &[1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0],
+            Self::Preset28 => &[1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0],
+            Self::Preset29 => &[1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0],
+            Self::Preset39 => &[1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0],
+        }
+    }
+}
+
+pub struct FxaaSettings {
+    pub quality: FxaaQuality,
+    /// Amount of subpixel aliasing removal, `FXAA_QUALITY__SUBPIX`.
+    pub subpixel_quality: f32,
+    /// Minimum local contrast relative to the brightest luma to count as an edge.
+    pub edge_threshold: f32,
+    /// Absolute minimum local contrast, skips dark areas.
+    pub edge_threshold_min: f32,
+    /// CAS sharpness applied where no edge is found, `None` to not sharpen.
+    pub sharpness: Option<f32>,
+}
+
+/// Threshold of the first glow downsample, compared against the exposed max RGB.
+#[derive(Clone, Copy)]
+pub enum GlowThreshold {
+    Smoothstep,
+    SoftKnee { threshold: f32, knee: f32 },
+}
+
+impl GlowThreshold {
+    /// `threshold_ev` is relative to an exposed value of 1.0, the knee starts `knee_ev`
+    /// stops below it and is symmetric around the threshold in linear space.
+    pub fn soft_knee_ev(threshold_ev: f32, knee_ev: f32) -> Self {
+        let threshold = threshold_ev.exp2();
+        let knee = threshold * (1.0 - (-knee_ev.max(0.0)).exp2());
+        Self::SoftKnee { threshold, knee }
+    }
 }
 
 #[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
@@ -507,6 +935,108 @@ pub enum GlowMode {
     Add,
     Replace,
     Mix,
+    Screen,
+    Softlight,
+}
+
+/// Derives the `b` and `c` coefficients of the Lottes curve so that `mid_in` maps to
+/// `mid_out` and `hdr_max` maps to 1.0.
+pub fn lottes_coefficients(
+    contrast: f32,
+    shoulder: f32,
+    hdr_max: f32,
+    mid_in: f32,
+    mid_out: f32,
+) -> (f32, f32) {
+    let a = contrast;
+    let ad = contrast * shoulder;
+    let denom = (hdr_max.powf(ad) - mid_in.powf(ad)) * mid_out;
+    let b = (-mid_in.powf(a) + hdr_max.powf(a) * mid_out) / denom;
+    let c =
+        (hdr_max.powf(ad) * mid_in.powf(a) - hdr_max.powf(a) * mid_in.powf(ad) * mid_out) / denom;
+    (b, c)
+}
+
+/// Hable's filmic curve, parameters as in `tonemap_hable` in `tonemap.glsl`.
+pub fn hable_curve(x: f32, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> f32 {
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+pub struct GradingSettings {
+    pub space: GradingSpace,
+    pub after_tonemap: bool,
+    /// White balance and channel mixer, in linear Rec.709.
+    pub color_matrix: Mat3,
+    pub lift: [f32; 3],
+    pub gamma: [f32; 3],
+    pub gain: [f32; 3],
+    pub cdl: Cdl,
+}
+
+pub struct VignetteSettings {
+    pub intensity: f32,
+    pub smoothness: f32,
+    /// 1.0 is a circle, lower values get closer to the screen rectangle.
+    pub roundness: f32,
+    /// Offset from the screen center in uv units.
+    pub center: Vector2,
+    /// Alpha fades the vignette out.
+    pub color: Color,
+    /// Multiplies the falloff, red channel, white if invalid.
+    pub mask_tex: Rid,
+    pub after_tonemap: bool,
+}
+
+pub struct GrainSettings {
+    pub intensity: f32,
+    /// Size of a grain in pixels.
+    pub size: f32,
+    /// 0 applies the same grain everywhere, 1 fades it out towards the highlights.
+    pub response: f32,
+    /// Tileable grain texture replacing the hash noise, red channel, unused if invalid.
+    pub tex: Rid,
+}
+
+pub struct ChromaticAberrationSettings {
+    pub intensity: f32,
+    pub samples: i32,
+    /// Weights of the samples from the center outwards, red to blue if invalid.
+    pub spectral_lut_tex: Rid,
+}
+
+/// Encoding of the color buffer for the display.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum OutputMode {
+    Sdr,
+    /// Linear Rec.709 with 1.0 at 80 nits, for float swapchains.
+    ScRgb,
+    /// PQ encoded Rec.2020.
+    Hdr10,
+}
+
+pub struct HdrOutputSettings {
+    /// `ScRgb` or `Hdr10`.
+    pub mode: OutputMode,
+    /// Luminance of a diffuse white, what SDR 1.0 is displayed at.
+    pub paper_white_nits: f32,
+    /// Peak luminance of the display. Reinhard, GT, Lottes and PBR Neutral roll off towards
+    /// it, the other tonemappers stay SDR-referred.
+    pub max_nits: f32,
+}
+
+impl HdrOutputSettings {
+    /// Brightest tonemapped value relative to paper white.
+    pub fn peak(&self) -> f32 {
+        display_peak(self.paper_white_nits, self.max_nits)
+    }
+}
+
+pub struct LensDistortionSettings {
+    /// Positive values give barrel, negative values pincushion distortion.
+    pub intensity: f32,
+    /// Zoom in so that barrel distortion doesn't sample outside of the screen.
+    pub crop_compensation: bool,
 }
 
 pub struct ToneMapSettings {
@@ -516,11 +1046,44 @@ pub struct ToneMapSettings {
     pub glow_map_tex: Rid,
     pub glow_intensity: f32,
     pub glow_map_strength: f32,
+    pub lens_dirt_tex: Rid,
+    /// Glow level the lens dirt is multiplied by.
+    pub lens_dirt_glow_tex: Rid,
+    pub lens_dirt_intensity: f32,
+    /// Result of the last `Streak` pass, already tinted.
+    pub streak_tex: Rid,
+    /// Output of `LensFlare`.
+    pub lens_flare_tex: Rid,
     pub exposure: f32,
     pub white: f32,
-    pub use_fxaa: bool,
+    pub fxaa: Option<FxaaSettings>,
     pub tonemap_type: ToneMapperType,
+    /// Operator specific parameters, see `PostEffectToneMap::tonemap_params`.
+    pub tonemap_params: [[f32; 4]; 3],
+    pub tonemap_mode: ToneMapMode,
+    /// Blend from the per-channel result to `tonemap_mode`.
+    pub tonemap_mode_blend: f32,
     pub glow_mode: GlowMode,
+    pub color_lut_tex: Rid,
+    pub color_lut_domain_min: [f32; 3],
+    pub color_lut_domain_max: [f32; 3],
+    pub custom_curve_tex: Rid,
+    /// Brightness, contrast and saturation, `None` if adjustments are disabled.
+    pub bcs: Option<[f32; 3]>,
+    /// A 2D (gradient) or 3D color correction texture.
+    pub color_correction_tex: Rid,
+    pub color_correction_is_1d: bool,
+    pub grading: Option<GradingSettings>,
+    pub vignette: Option<VignetteSettings>,
+    pub grain: Option<GrainSettings>,
+    /// Incremented every frame, seeds the grain and dither noise.
+    pub frame: u32,
+    /// Bits per channel of the output, `None` disables debanding.
+    pub debanding_bit_depth: Option<u32>,
+    pub chromatic_aberration: Option<ChromaticAberrationSettings>,
+    pub lens_distortion: Option<LensDistortionSettings>,
+    /// `None` outputs SDR.
+    pub hdr_output: Option<HdrOutputSettings>,
 }
 
 pub struct ToneMapper {
@@ -529,9 +1092,28 @@ pub struct ToneMapper {
     ubo: PackedArray<u8>,
     uniforms_src: Array<Gd<RdUniform>>,
     uniforms_glow: Array<Gd<RdUniform>>,
+    uniforms_grading: Array<Gd<RdUniform>>,
+    uniforms_params: Array<Gd<RdUniform>>,
+    grading_ubo: PackedArray<u8>,
+    grading_buffer: Rid,
+    effects_ubo: PackedArray<u8>,
+    effects_buffer: Rid,
     sampler: Rid,
     sampler_mipmaps: Rid,
     default_tex_white: Rid,
+    default_tex_3d_white: Rid,
+    default_tex_1d_white: Rid,
+}
+
+impl Drop for ToneMapper {
+    fn drop(&mut self) {
+        if self.grading_buffer.is_valid() {
+            self.renderer.rd.free_rid(self.grading_buffer);
+        }
+        if self.effects_buffer.is_valid() {
+            self.renderer.rd.free_rid(self.effects_buffer);
+        }
+    }
 }
 
 impl ToneMapper {
@@ -563,6 +1145,81 @@ impl ToneMapper {
         uniform_glow_map_tex.set_binding(1);
         uniforms_glow.push(&uniform_glow_tex);
         uniforms_glow.push(&uniform_glow_map_tex);
+        let mut uniform_lens_dirt_tex = RdUniform::new_gd();
+        uniform_lens_dirt_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_lens_dirt_tex.set_binding(2);
+        uniforms_glow.push(&uniform_lens_dirt_tex);
+        let mut uniform_lens_dirt_glow_tex = RdUniform::new_gd();
+        uniform_lens_dirt_glow_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_lens_dirt_glow_tex.set_binding(3);
+        uniforms_glow.push(&uniform_lens_dirt_glow_tex);
+        let mut uniform_streak_tex = RdUniform::new_gd();
+        uniform_streak_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_streak_tex.set_binding(4);
+        uniforms_glow.push(&uniform_streak_tex);
+        let mut uniform_lens_flare_tex = RdUniform::new_gd();
+        uniform_lens_flare_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_lens_flare_tex.set_binding(5);
+        uniforms_glow.push(&uniform_lens_flare_tex);
+        let mut uniform_vignette_mask_tex = RdUniform::new_gd();
+        uniform_vignette_mask_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_vignette_mask_tex.set_binding(6);
+        uniforms_glow.push(&uniform_vignette_mask_tex);
+        let mut uniform_grain_tex = RdUniform::new_gd();
+        uniform_grain_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_grain_tex.set_binding(7);
+        uniforms_glow.push(&uniform_grain_tex);
+        let mut uniform_spectral_lut_tex = RdUniform::new_gd();
+        uniform_spectral_lut_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_spectral_lut_tex.set_binding(8);
+        uniforms_glow.push(&uniform_spectral_lut_tex);
+
+        let mut uniforms_grading = Array::new();
+        let mut uniform_color_lut_tex = RdUniform::new_gd();
+        uniform_color_lut_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_color_lut_tex.set_binding(0);
+        uniforms_grading.push(&uniform_color_lut_tex);
+        let mut uniform_custom_curve_tex = RdUniform::new_gd();
+        uniform_custom_curve_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_custom_curve_tex.set_binding(1);
+        uniforms_grading.push(&uniform_custom_curve_tex);
+        let mut uniform_color_correction_1d_tex = RdUniform::new_gd();
+        uniform_color_correction_1d_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_color_correction_1d_tex.set_binding(2);
+        uniforms_grading.push(&uniform_color_correction_1d_tex);
+        let mut uniform_color_correction_tex = RdUniform::new_gd();
+        uniform_color_correction_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_color_correction_tex.set_binding(3);
+        uniforms_grading.push(&uniform_color_correction_tex);
+
+        let grading_ubo_bytes: [u8; std::mem::size_of::<ToneMapperGradingUniforms>()] =
+            zerocopy::transmute!(ToneMapperGradingUniforms::default());
+        let grading_ubo = PackedArray::<u8>::from(&grading_ubo_bytes);
+        let mut renderer = Raster::load_shader_file_path(TONEMAPPER_SHADER_PATH);
+        let grading_buffer = renderer
+            .rd
+            .uniform_buffer_create_ex(grading_ubo.len().try_into().unwrap())
+            .data(&grading_ubo)
+            .done();
+        let effects_ubo_bytes: [u8; std::mem::size_of::<ToneMapperEffectsUniforms>()] =
+            zerocopy::transmute!(ToneMapperEffectsUniforms::default());
+        let effects_ubo = PackedArray::<u8>::from(&effects_ubo_bytes);
+        let effects_buffer = renderer
+            .rd
+            .uniform_buffer_create_ex(effects_ubo.len().try_into().unwrap())
+            .data(&effects_ubo)
+            .done();
+        let mut uniforms_params = Array::new();
+        let mut uniform_grading_buffer = RdUniform::new_gd();
+        uniform_grading_buffer.set_uniform_type(UniformType::UNIFORM_BUFFER);
+        uniform_grading_buffer.set_binding(0);
+        uniform_grading_buffer.add_id(grading_buffer);
+        uniforms_params.push(&uniform_grading_buffer);
+        let mut uniform_effects_buffer = RdUniform::new_gd();
+        uniform_effects_buffer.set_uniform_type(UniformType::UNIFORM_BUFFER);
+        uniform_effects_buffer.set_binding(1);
+        uniform_effects_buffer.add_id(effects_buffer);
+        uniforms_params.push(&uniform_effects_buffer);
 
         let singleton = Engine::singleton()
             .get_singleton(&GlobalRidsSingleton::class_name().to_string_name())
@@ -571,16 +1228,26 @@ impl ToneMapper {
         let sampler = singleton.bind().default_sampler;
         let sampler_mipmaps = singleton.bind().default_sampler_mipmaps;
         let default_tex_white = singleton.bind().default_texture_white;
+        let default_tex_3d_white = singleton.bind().default_texture_3d_white;
+        let default_tex_1d_white = singleton.bind().default_texture_1d_white;
 
         Self {
-            renderer: Raster::load_shader_file_path(TONEMAPPER_SHADER_PATH),
+            renderer,
             scs,
             ubo,
             uniforms_src,
             uniforms_glow,
+            uniforms_grading,
+            uniforms_params,
+            grading_ubo,
+            grading_buffer,
+            effects_ubo,
+            effects_buffer,
             sampler,
             sampler_mipmaps,
             default_tex_white,
+            default_tex_3d_white,
+            default_tex_1d_white,
         }
     }
     pub fn exec(
@@ -603,7 +1270,7 @@ impl ToneMapper {
         self.scs
             .get(1)
             .unwrap()
-            .set_value(&settings.use_fxaa.to_variant());
+            .set_value(&settings.fxaa.is_some().to_variant());
         self.scs
             .get(settings.tonemap_type as usize + Into::<usize>::into(SC_TONEMAP_TYPE_INDEX))
             .unwrap()
@@ -612,6 +1279,117 @@ impl ToneMapper {
             .get(settings.glow_mode as usize + Into::<usize>::into(SC_GLOW_MODE_INDEX))
             .unwrap()
             .set_value(&tv);
+        self.scs
+            .get(settings.tonemap_mode as usize + Into::<usize>::into(SC_TONEMAP_MODE_INDEX))
+            .unwrap()
+            .set_value(&tv);
+        self.scs
+            .get(SC_USE_COLOR_LUT_INDEX.into())
+            .unwrap()
+            .set_value(&settings.color_lut_tex.is_valid().to_variant());
+        self.scs
+            .get(SC_USE_BCS_INDEX.into())
+            .unwrap()
+            .set_value(&settings.bcs.is_some().to_variant());
+        self.scs
+            .get(SC_USE_COLOR_CORRECTION_INDEX.into())
+            .unwrap()
+            .set_value(&settings.color_correction_tex.is_valid().to_variant());
+        self.scs
+            .get(SC_USE_1D_COLOR_CORRECTION_INDEX.into())
+            .unwrap()
+            .set_value(&settings.color_correction_is_1d.to_variant());
+        self.scs
+            .get(SC_USE_LENS_DIRT_INDEX.into())
+            .unwrap()
+            .set_value(
+                &(settings.lens_dirt_tex.is_valid() && settings.lens_dirt_glow_tex.is_valid())
+                    .to_variant(),
+            );
+        self.scs
+            .get(SC_USE_STREAK_INDEX.into())
+            .unwrap()
+            .set_value(&settings.streak_tex.is_valid().to_variant());
+        self.scs
+            .get(SC_USE_LENS_FLARE_INDEX.into())
+            .unwrap()
+            .set_value(&settings.lens_flare_tex.is_valid().to_variant());
+        if let Some(grading) = &settings.grading {
+            self.scs
+                .get(SC_USE_GRADING_INDEX.into())
+                .unwrap()
+                .set_value(&tv);
+            self.scs
+                .get(SC_GRADING_ACESCC_INDEX.into())
+                .unwrap()
+                .set_value(&(grading.space == GradingSpace::AcesCc).to_variant());
+            self.scs
+                .get(SC_GRADING_AFTER_TONEMAP_INDEX.into())
+                .unwrap()
+                .set_value(&grading.after_tonemap.to_variant());
+        }
+        if let Some(vignette) = &settings.vignette {
+            self.scs
+                .get(SC_USE_VIGNETTE_INDEX.into())
+                .unwrap()
+                .set_value(&tv);
+            self.scs
+                .get(SC_VIGNETTE_AFTER_TONEMAP_INDEX.into())
+                .unwrap()
+                .set_value(&vignette.after_tonemap.to_variant());
+        }
+        self.scs
+            .get(SC_USE_DEBANDING_INDEX.into())
+            .unwrap()
+            .set_value(&settings.debanding_bit_depth.is_some().to_variant());
+        if let Some(chromatic_aberration) = &settings.chromatic_aberration {
+            self.scs
+                .get(SC_USE_CHROMATIC_ABERRATION_INDEX.into())
+                .unwrap()
+                .set_value(&tv);
+            self.scs
+                .get(SC_USE_SPECTRAL_LUT_INDEX.into())
+                .unwrap()
+                .set_value(
+                    &chromatic_aberration
+                        .spectral_lut_tex
+                        .is_valid()
+                        .to_variant(),
+                );
+        }
+        self.scs
+            .get(SC_USE_FXAA_SHARPEN_INDEX.into())
+            .unwrap()
+            .set_value(
+                &settings
+                    .fxaa
+                    .as_ref()
+                    .is_some_and(|fxaa| fxaa.sharpness.is_some())
+                    .to_variant(),
+            );
+        self.scs
+            .get(SC_USE_LENS_DISTORTION_INDEX.into())
+            .unwrap()
+            .set_value(&settings.lens_distortion.is_some().to_variant());
+        let output_mode = settings.hdr_output.as_ref().map(|hdr| hdr.mode);
+        self.scs
+            .get(SC_OUTPUT_SCRGB_INDEX.into())
+            .unwrap()
+            .set_value(&(output_mode == Some(OutputMode::ScRgb)).to_variant());
+        self.scs
+            .get(SC_OUTPUT_HDR10_INDEX.into())
+            .unwrap()
+            .set_value(&(output_mode == Some(OutputMode::Hdr10)).to_variant());
+        if let Some(grain) = &settings.grain {
+            self.scs
+                .get(SC_USE_GRAIN_INDEX.into())
+                .unwrap()
+                .set_value(&tv);
+            self.scs
+                .get(SC_USE_GRAIN_TEXTURE_INDEX.into())
+                .unwrap()
+                .set_value(&grain.tex.is_valid().to_variant());
+        }
 
         // Pipeline.
         self.renderer
@@ -625,8 +1403,18 @@ impl ToneMapper {
         ubo_mut.glow_pixel_size_y = 1.0 / settings.glow_tex_size.y as f32;
         ubo_mut.glow_intensity = settings.glow_intensity;
         ubo_mut.glow_map_strength = settings.glow_map_strength;
+        ubo_mut.lens_dirt_intensity = settings.lens_dirt_intensity;
         ubo_mut.exposure = settings.exposure;
         ubo_mut.white = settings.white;
+        for i in 0..3 {
+            ubo_mut.color_lut_domain_min[i] = settings.color_lut_domain_min[i];
+            ubo_mut.color_lut_domain_scale[i] =
+                1.0 / (settings.color_lut_domain_max[i] - settings.color_lut_domain_min[i]);
+        }
+        ubo_mut.tonemap_params = settings.tonemap_params;
+        ubo_mut.tonemap_mode_blend = settings.tonemap_mode_blend;
+        ubo_mut.bcs = settings.bcs.unwrap_or([1.0; 3]);
+        ubo_mut.frame = settings.frame;
 
         let mut uniform_src_tex = self.uniforms_src.get(0).unwrap();
         uniform_src_tex.clear_ids();
@@ -647,10 +1435,211 @@ impl ToneMapper {
             uniform_glow_map_tex.add_id(self.default_tex_white);
         }
 
+        let use_lens_dirt =
+            settings.lens_dirt_tex.is_valid() && settings.lens_dirt_glow_tex.is_valid();
+        let mut uniform_lens_dirt_tex = self.uniforms_glow.get(2).unwrap();
+        uniform_lens_dirt_tex.clear_ids();
+        uniform_lens_dirt_tex.add_id(self.sampler);
+        let mut uniform_lens_dirt_glow_tex = self.uniforms_glow.get(3).unwrap();
+        uniform_lens_dirt_glow_tex.clear_ids();
+        uniform_lens_dirt_glow_tex.add_id(self.sampler);
+        if use_lens_dirt {
+            uniform_lens_dirt_tex.add_id(settings.lens_dirt_tex);
+            uniform_lens_dirt_glow_tex.add_id(settings.lens_dirt_glow_tex);
+        } else {
+            uniform_lens_dirt_tex.add_id(self.default_tex_white);
+            uniform_lens_dirt_glow_tex.add_id(self.default_tex_white);
+        }
+
+        let mut uniform_streak_tex = self.uniforms_glow.get(4).unwrap();
+        uniform_streak_tex.clear_ids();
+        uniform_streak_tex.add_id(self.sampler);
+        if settings.streak_tex.is_valid() {
+            uniform_streak_tex.add_id(settings.streak_tex);
+        } else {
+            uniform_streak_tex.add_id(self.default_tex_white);
+        }
+
+        let mut uniform_lens_flare_tex = self.uniforms_glow.get(5).unwrap();
+        uniform_lens_flare_tex.clear_ids();
+        uniform_lens_flare_tex.add_id(self.sampler);
+        if settings.lens_flare_tex.is_valid() {
+            uniform_lens_flare_tex.add_id(settings.lens_flare_tex);
+        } else {
+            uniform_lens_flare_tex.add_id(self.default_tex_white);
+        }
+
+        let mut uniform_vignette_mask_tex = self.uniforms_glow.get(6).unwrap();
+        uniform_vignette_mask_tex.clear_ids();
+        uniform_vignette_mask_tex.add_id(self.sampler);
+        match &settings.vignette {
+            Some(vignette) if vignette.mask_tex.is_valid() => {
+                uniform_vignette_mask_tex.add_id(vignette.mask_tex);
+            }
+            _ => uniform_vignette_mask_tex.add_id(self.default_tex_white),
+        }
+
+        let mut uniform_grain_tex = self.uniforms_glow.get(7).unwrap();
+        uniform_grain_tex.clear_ids();
+        uniform_grain_tex.add_id(self.sampler);
+        match &settings.grain {
+            Some(grain) if grain.tex.is_valid() => uniform_grain_tex.add_id(grain.tex),
+            _ => uniform_grain_tex.add_id(self.default_tex_white),
+        }
+
+        let mut uniform_spectral_lut_tex = self.uniforms_glow.get(8).unwrap();
+        uniform_spectral_lut_tex.clear_ids();
+        uniform_spectral_lut_tex.add_id(self.sampler);
+        match &settings.chromatic_aberration {
+            Some(chromatic_aberration) if chromatic_aberration.spectral_lut_tex.is_valid() => {
+                uniform_spectral_lut_tex.add_id(chromatic_aberration.spectral_lut_tex);
+            }
+            _ => uniform_spectral_lut_tex.add_id(self.default_tex_white),
+        }
+
+        let mut uniform_color_lut_tex = self.uniforms_grading.get(0).unwrap();
+        uniform_color_lut_tex.clear_ids();
+        uniform_color_lut_tex.add_id(self.sampler);
+        if settings.color_lut_tex.is_valid() {
+            uniform_color_lut_tex.add_id(settings.color_lut_tex);
+        } else {
+            uniform_color_lut_tex.add_id(self.default_tex_3d_white);
+        }
+
+        let mut uniform_custom_curve_tex = self.uniforms_grading.get(1).unwrap();
+        uniform_custom_curve_tex.clear_ids();
+        uniform_custom_curve_tex.add_id(self.sampler);
+        if settings.custom_curve_tex.is_valid() {
+            uniform_custom_curve_tex.add_id(settings.custom_curve_tex);
+        } else {
+            uniform_custom_curve_tex.add_id(self.default_tex_1d_white);
+        }
+
+        let mut uniform_color_correction_1d_tex = self.uniforms_grading.get(2).unwrap();
+        uniform_color_correction_1d_tex.clear_ids();
+        uniform_color_correction_1d_tex.add_id(self.sampler);
+        let mut uniform_color_correction_tex = self.uniforms_grading.get(3).unwrap();
+        uniform_color_correction_tex.clear_ids();
+        uniform_color_correction_tex.add_id(self.sampler);
+        if settings.color_correction_tex.is_valid() && settings.color_correction_is_1d {
+            uniform_color_correction_1d_tex.add_id(settings.color_correction_tex);
+            uniform_color_correction_tex.add_id(self.default_tex_3d_white);
+        } else if settings.color_correction_tex.is_valid() {
+            uniform_color_correction_1d_tex.add_id(self.default_tex_white);
+            uniform_color_correction_tex.add_id(settings.color_correction_tex);
+        } else {
+            uniform_color_correction_1d_tex.add_id(self.default_tex_white);
+            uniform_color_correction_tex.add_id(self.default_tex_3d_white);
+        }
+
+        if let Some(grading) = &settings.grading {
+            let grading_ubo = self.grading_ubo.as_mut_slice();
+            let grading_ubo_mut = ToneMapperGradingUniforms::mut_from_bytes(grading_ubo).unwrap();
+            for (dst, src) in grading_ubo_mut
+                .color_matrix
+                .iter_mut()
+                .zip(grading.color_matrix)
+            {
+                *dst = [src[0], src[1], src[2], 0.0];
+            }
+            let [r, g, b] = grading.lift;
+            grading_ubo_mut.lift = [r, g, b, 0.0];
+            let [r, g, b] = grading.gamma.map(|g| 1.0 / g.max(1e-3));
+            grading_ubo_mut.inv_gamma = [r, g, b, 0.0];
+            let [r, g, b] = grading.gain;
+            grading_ubo_mut.gain = [r, g, b, 0.0];
+            let [r, g, b] = grading.cdl.slope;
+            grading_ubo_mut.cdl_slope = [r, g, b, grading.cdl.saturation];
+            let [r, g, b] = grading.cdl.offset;
+            grading_ubo_mut.cdl_offset = [r, g, b, 0.0];
+            let [r, g, b] = grading.cdl.power;
+            grading_ubo_mut.cdl_power = [r, g, b, 0.0];
+            self.renderer.rd.buffer_update(
+                self.grading_buffer,
+                0,
+                self.grading_ubo.len().try_into().unwrap(),
+                &self.grading_ubo,
+            );
+        }
+
+        let effects_ubo = self.effects_ubo.as_mut_slice();
+        let effects_ubo_mut = ToneMapperEffectsUniforms::mut_from_bytes(effects_ubo).unwrap();
+        if let Some(vignette) = &settings.vignette {
+            let color = vignette.color;
+            effects_ubo_mut.vignette_color = [color.r, color.g, color.b, color.a];
+            effects_ubo_mut.vignette_center = [vignette.center.x, vignette.center.y];
+            effects_ubo_mut.vignette_scale = vignette.intensity.max(0.0) * 3.0;
+            effects_ubo_mut.vignette_smoothness = vignette.smoothness.max(1e-3) * 5.0;
+            let roundness = vignette.roundness.clamp(0.0, 1.0);
+            effects_ubo_mut.vignette_roundness = 6.0 * (1.0 - roundness) + roundness;
+        }
+        if let Some(grain) = &settings.grain {
+            effects_ubo_mut.grain_intensity = grain.intensity.max(0.0);
+            effects_ubo_mut.grain_size = grain.size.max(1.0);
+            effects_ubo_mut.grain_response = grain.response.clamp(0.0, 1.0);
+        }
+        if let Some(bit_depth) = settings.debanding_bit_depth {
+            // One step of the output encoding.
+            effects_ubo_mut.debanding_scale = 1.0 / ((1u64 << bit_depth) - 1) as f32;
+        }
+        if let Some(chromatic_aberration) = &settings.chromatic_aberration {
+            effects_ubo_mut.chromatic_aberration_amount = chromatic_aberration.intensity * 0.05;
+            effects_ubo_mut.chromatic_aberration_samples =
+                chromatic_aberration.samples.clamp(3, 32);
+        }
+        if let Some(lens_distortion) = &settings.lens_distortion {
+            effects_ubo_mut.lens_distortion_intensity = lens_distortion.intensity;
+            // The corners are at radius 1 and move the furthest.
+            effects_ubo_mut.lens_distortion_scale = if lens_distortion.crop_compensation {
+                1.0 / (1.0 + lens_distortion.intensity).max(1.0)
+            } else {
+                1.0
+            };
+        }
+        if let Some(hdr) = &settings.hdr_output {
+            effects_ubo_mut.output_peak = hdr.peak();
+            effects_ubo_mut.output_scale = match hdr.mode {
+                OutputMode::Hdr10 => pq_scale(hdr.paper_white_nits),
+                OutputMode::ScRgb | OutputMode::Sdr => scrgb_scale(hdr.paper_white_nits),
+            };
+        }
+        if let Some(fxaa) = &settings.fxaa {
+            if let Some(sharpness) = fxaa.sharpness {
+                effects_ubo_mut.fxaa_sharpen_peak = cas_peak(sharpness);
+            }
+            effects_ubo_mut.fxaa_edge_threshold_min = fxaa.edge_threshold_min.max(0.0);
+            effects_ubo_mut.fxaa_edge_threshold_max = fxaa.edge_threshold.max(0.0);
+            effects_ubo_mut.fxaa_subpixel_quality = fxaa.subpixel_quality.clamp(0.0, 1.0);
+            let steps = fxaa.quality.steps();
+            effects_ubo_mut.fxaa_iterations = steps.len().try_into().unwrap();
+            for (i, step) in steps.iter().enumerate() {
+                effects_ubo_mut.fxaa_steps[i / 4][i % 4] = *step;
+            }
+        }
+        if settings.vignette.is_some()
+            || settings.grain.is_some()
+            || settings.fxaa.is_some()
+            || settings.hdr_output.is_some()
+            || settings.debanding_bit_depth.is_some()
+            || settings.chromatic_aberration.is_some()
+            || settings.lens_distortion.is_some()
+        {
+            self.renderer.rd.buffer_update(
+                self.effects_buffer,
+                0,
+                self.effects_ubo.len().try_into().unwrap(),
+                &self.effects_ubo,
+            );
+        }
+
         let uniform_set0 =
             UniformSetCacheRd::get_cache(self.renderer.shader, 0, &self.uniforms_src);
         let uniform_set1 =
             UniformSetCacheRd::get_cache(self.renderer.shader, 1, &self.uniforms_glow);
+        let uniform_set2 =
+            UniformSetCacheRd::get_cache(self.renderer.shader, 2, &self.uniforms_grading);
+        let uniform_set3 =
+            UniformSetCacheRd::get_cache(self.renderer.shader, 3, &self.uniforms_params);
 
         let draw_list = self.renderer.rd.draw_list_begin(self.renderer.framebuffer);
         self.renderer
@@ -667,6 +1656,12 @@ impl ToneMapper {
         self.renderer
             .rd
             .draw_list_bind_uniform_set(draw_list, uniform_set1, 1);
+        self.renderer
+            .rd
+            .draw_list_bind_uniform_set(draw_list, uniform_set2, 2);
+        self.renderer
+            .rd
+            .draw_list_bind_uniform_set(draw_list, uniform_set3, 3);
         self.renderer
             .rd
             .draw_list_draw_ex(draw_list, false, 1)
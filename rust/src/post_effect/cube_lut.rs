@@ -0,0 +1,327 @@
+use std::fmt;
+
+/// Largest lattice accepted for 3D LUTs, and the size 1D LUTs are baked into.
+pub const MAX_LUT_SIZE: usize = 65;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeLutKind {
+    Lut1d,
+    Lut3d,
+}
+
+/// A parsed Adobe/Resolve `.cube` LUT.
+///
+/// `table` holds `size` entries for 1D LUTs and `size^3` entries for 3D LUTs,
+/// with red varying fastest, then green, then blue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeLut {
+    pub kind: CubeLutKind,
+    pub size: usize,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    pub table: Vec<[f32; 3]>,
+    /// Unknown keywords that were skipped, like the video range flags of Resolve exports.
+    pub warnings: Vec<CubeLutError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeLutError {
+    /// 1-based line number, 0 if the error is not tied to a line.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CubeLutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}: {}", self.line, self.message)
+        } else {
+            f.write_str(&self.message)
+        }
+    }
+}
+
+impl std::error::Error for CubeLutError {}
+
+fn err<T>(line: usize, message: impl Into<String>) -> Result<T, CubeLutError> {
+    Err(CubeLutError {
+        line,
+        message: message.into(),
+    })
+}
+
+fn parse_floats<const N: usize>(
+    line: usize,
+    keyword: &str,
+    args: &[&str],
+) -> Result<[f32; N], CubeLutError> {
+    if args.len() != N {
+        return err(
+            line,
+            format!("{keyword} expects {N} values, got {}", args.len()),
+        );
+    }
+    let mut out = [0.0; N];
+    for (o, a) in out.iter_mut().zip(args) {
+        *o = match a.parse::<f32>() {
+            Ok(v) if v.is_finite() => v,
+            _ => return err(line, format!("invalid number '{a}' in {keyword}")),
+        };
+    }
+    Ok(out)
+}
+
+fn parse_size(
+    line: usize,
+    keyword: &str,
+    args: &[&str],
+    max: usize,
+) -> Result<usize, CubeLutError> {
+    let [arg] = args else {
+        return err(
+            line,
+            format!("{keyword} expects 1 value, got {}", args.len()),
+        );
+    };
+    match arg.parse::<usize>() {
+        Ok(size) if (2..=max).contains(&size) => Ok(size),
+        Ok(size) => err(line, format!("{keyword} {size} is out of range [2, {max}]")),
+        Err(_) => err(line, format!("invalid size '{arg}' in {keyword}")),
+    }
+}
+
+impl CubeLut {
+    pub fn parse(src: &str) -> Result<Self, CubeLutError> {
+        let mut kind = None;
+        let mut size = 0;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+        let mut warnings = Vec::new();
+
+        for (index, raw) in src.lines().enumerate() {
+            let line = index + 1;
+            // The title is a free-form quoted string, a '#' in it doesn't start a comment.
+            let text = if raw.split_whitespace().next() == Some("TITLE") {
+                raw.trim()
+            } else {
+                raw.split('#').next().unwrap().trim()
+            };
+            if text.is_empty() {
+                continue;
+            }
+            let mut tokens = text.split_whitespace();
+            let keyword = tokens.next().unwrap();
+            let args: Vec<&str> = tokens.collect();
+
+            if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                if !table.is_empty() {
+                    return err(line, format!("keyword {keyword} after table data"));
+                }
+                match keyword {
+                    // The title is a free-form quoted string.
+                    "TITLE" => {}
+                    "LUT_1D_SIZE" | "LUT_3D_SIZE" => {
+                        if kind.is_some() {
+                            return err(line, "only one of LUT_1D_SIZE or LUT_3D_SIZE is allowed");
+                        }
+                        if keyword == "LUT_1D_SIZE" {
+                            kind = Some(CubeLutKind::Lut1d);
+                            size = parse_size(line, keyword, &args, 65536)?;
+                        } else {
+                            kind = Some(CubeLutKind::Lut3d);
+                            size = parse_size(line, keyword, &args, MAX_LUT_SIZE)?;
+                        }
+                    }
+                    "DOMAIN_MIN" => domain_min = parse_floats(line, keyword, &args)?,
+                    "DOMAIN_MAX" => domain_max = parse_floats(line, keyword, &args)?,
+                    // Resolve writes these instead of DOMAIN_MIN/DOMAIN_MAX.
+                    "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                        let [min, max] = parse_floats(line, keyword, &args)?;
+                        domain_min = [min; 3];
+                        domain_max = [max; 3];
+                    }
+                    _ => warnings.push(CubeLutError {
+                        line,
+                        message: format!("unknown keyword {keyword}, skipped"),
+                    }),
+                }
+                continue;
+            }
+
+            let Some(kind) = kind else {
+                return err(line, "table data before LUT_1D_SIZE or LUT_3D_SIZE");
+            };
+            let mut values = vec![keyword];
+            values.extend(args);
+            table.push(parse_floats::<3>(line, "table entry", &values)?);
+            let expected = match kind {
+                CubeLutKind::Lut1d => size,
+                CubeLutKind::Lut3d => size * size * size,
+            };
+            if table.len() > expected {
+                return err(line, format!("too many table entries, expected {expected}"));
+            }
+        }
+
+        let Some(kind) = kind else {
+            return err(0, "missing LUT_1D_SIZE or LUT_3D_SIZE");
+        };
+        let expected = match kind {
+            CubeLutKind::Lut1d => size,
+            CubeLutKind::Lut3d => size * size * size,
+        };
+        if table.len() != expected {
+            return err(
+                0,
+                format!("expected {expected} table entries, got {}", table.len()),
+            );
+        }
+        for c in 0..3 {
+            if domain_min[c] >= domain_max[c] {
+                return err(0, "DOMAIN_MIN must be less than DOMAIN_MAX");
+            }
+        }
+
+        Ok(Self {
+            kind,
+            size,
+            domain_min,
+            domain_max,
+            table,
+            warnings,
+        })
+    }
+
+    /// Edge length of the 3D texture this LUT is uploaded as.
+    pub fn texture_size(&self) -> usize {
+        match self.kind {
+            CubeLutKind::Lut1d => self.size.min(MAX_LUT_SIZE),
+            CubeLutKind::Lut3d => self.size,
+        }
+    }
+
+    fn sample_1d(&self, channel: usize, t: f32) -> f32 {
+        let x = t.clamp(0.0, 1.0) * (self.size - 1) as f32;
+        let i = (x as usize).min(self.size - 2);
+        let f = x - i as f32;
+        self.table[i][channel] * (1.0 - f) + self.table[i + 1][channel] * f
+    }
+
+    /// RGBA texel data for a `texture_size()^3` 3D texture.
+    ///
+    /// 1D LUTs are separable, so baking them into the lattice keeps trilinear
+    /// filtering exact at the baked resolution.
+    pub fn to_rgba(&self) -> Vec<[f32; 4]> {
+        match self.kind {
+            CubeLutKind::Lut3d => self.table.iter().map(|c| [c[0], c[1], c[2], 1.0]).collect(),
+            CubeLutKind::Lut1d => {
+                let n = self.texture_size();
+                let step = 1.0 / (n - 1) as f32;
+                let mut out = Vec::with_capacity(n * n * n);
+                for b in 0..n {
+                    for g in 0..n {
+                        for r in 0..n {
+                            out.push([
+                                self.sample_1d(0, r as f32 * step),
+                                self.sample_1d(1, g as f32 * step),
+                                self.sample_1d(2, b as f32 * step),
+                                1.0,
+                            ]);
+                        }
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_3d_identity() {
+        let src = "TITLE \"identity\"\n# comment\nLUT_3D_SIZE 2\n\n\
+            0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+        let lut = CubeLut::parse(src).unwrap();
+        assert_eq!(lut.kind, CubeLutKind::Lut3d);
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.domain_min, [0.0; 3]);
+        assert_eq!(lut.domain_max, [1.0; 3]);
+        assert_eq!(lut.table[1], [1.0, 0.0, 0.0]);
+        assert_eq!(lut.to_rgba()[6], [0.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn parses_1d_with_domain() {
+        let src = "LUT_1D_SIZE 3\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 2 2 2\n0 0 0\n0.5 0.25 1\n1 1 1\n";
+        let lut = CubeLut::parse(src).unwrap();
+        assert_eq!(lut.kind, CubeLutKind::Lut1d);
+        assert_eq!(lut.domain_max, [2.0; 3]);
+        assert_eq!(lut.texture_size(), 3);
+        let rgba = lut.to_rgba();
+        assert_eq!(rgba.len(), 27);
+        // r = 1, g = 0, b = 2 on the lattice.
+        assert_eq!(rgba[1 + 2 * 9], [0.5, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn accepts_resolve_input_range() {
+        let src = "LUT_1D_SIZE 2\nLUT_1D_INPUT_RANGE -0.5 1.5\n0 0 0\n1 1 1\n";
+        let lut = CubeLut::parse(src).unwrap();
+        assert_eq!(lut.domain_min, [-0.5; 3]);
+        assert_eq!(lut.domain_max, [1.5; 3]);
+    }
+
+    #[test]
+    fn skips_unknown_keywords() {
+        let src = "TITLE \"Log #2 to Rec.709\"\nLUT_1D_SIZE 2\nLUT_IN_VIDEO_RANGE\n\
+            LUT_OUT_VIDEO_RANGE\n0 0 0\n1 1 1\n";
+        let lut = CubeLut::parse(src).unwrap();
+        assert_eq!(lut.table, [[0.0; 3], [1.0; 3]]);
+        let lines: Vec<usize> = lut.warnings.iter().map(|w| w.line).collect();
+        assert_eq!(lines, [3, 4]);
+        assert_eq!(
+            lut.warnings[0].to_string(),
+            "line 3: unknown keyword LUT_IN_VIDEO_RANGE, skipped"
+        );
+    }
+
+    #[test]
+    fn keeps_hash_in_title() {
+        let lut = CubeLut::parse("TITLE \"#1 # 2\"\nLUT_1D_SIZE 2\n0 0 0\n1 1 1\n").unwrap();
+        assert!(lut.warnings.is_empty());
+        // Comments still end other lines.
+        let lut = CubeLut::parse("LUT_1D_SIZE 2 # size\n0 0 0 # black\n1 1 1\n").unwrap();
+        assert_eq!(lut.size, 2);
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let e = CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n1 x 0\n").unwrap_err();
+        assert_eq!(e.line, 3);
+        assert_eq!(e.to_string(), "line 3: invalid number 'x' in table entry");
+
+        let e = CubeLut::parse("LUT_3D_SIZE 66\n").unwrap_err();
+        assert_eq!(e.line, 1);
+
+        let e = CubeLut::parse("0 0 0\n").unwrap_err();
+        assert_eq!(e.line, 1);
+
+        let e = CubeLut::parse("LUT_3D_SIZE 2\nDOMAIN_MIN 0 0\n").unwrap_err();
+        assert_eq!(e.line, 2);
+
+        let e = CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n0 0\n").unwrap_err();
+        assert_eq!(e.line, 3);
+    }
+
+    #[test]
+    fn rejects_incomplete_tables() {
+        let e = CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n").unwrap_err();
+        assert_eq!(e.line, 0);
+        assert!(CubeLut::parse("").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\nDOMAIN_MIN 1 1 1\n0 0 0\n1 1 1\n").is_err());
+    }
+}
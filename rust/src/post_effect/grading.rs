@@ -0,0 +1,331 @@
+use std::fmt;
+
+pub type Mat3 = [[f32; 3]; 3];
+
+pub const IDENTITY: Mat3 = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Color temperature white balance is relative to, it maps to the identity.
+pub const NEUTRAL_TEMPERATURE: f32 = 6500.0;
+
+const REC709_TO_XYZ: Mat3 = [
+    [0.412_456_4, 0.357_576_1, 0.180_437_5],
+    [0.212_672_9, 0.715_152_2, 0.072_175],
+    [0.019_333_9, 0.119_192, 0.950_304_1],
+];
+
+const XYZ_TO_REC709: Mat3 = [
+    [3.240_454_2, -1.537_138_5, -0.498_531_4],
+    [-0.969_266, 1.876_010_8, 0.041_556],
+    [0.055_643_4, -0.204_025_9, 1.057_225_2],
+];
+
+/// CIECAM02 chromatic adaptation transform, XYZ to LMS.
+const XYZ_TO_LMS: Mat3 = [
+    [0.7328, 0.4296, -0.1624],
+    [-0.7036, 1.6975, 0.0061],
+    [0.0030, 0.0136, 0.9834],
+];
+
+const LMS_TO_XYZ: Mat3 = [
+    [1.096_124, -0.278_869, 0.182_745],
+    [0.454_369, 0.473_533, 0.072_098],
+    [-0.009_628, -0.005_698, 1.015_326],
+];
+
+pub fn mul(a: &Mat3, b: &Mat3) -> Mat3 {
+    let mut out = [[0.0; 3]; 3];
+    for (r, row) in out.iter_mut().enumerate() {
+        for (c, v) in row.iter_mut().enumerate() {
+            *v = (0..3).map(|k| a[r][k] * b[k][c]).sum();
+        }
+    }
+    out
+}
+
+pub fn mul_vec(m: &Mat3, v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+/// CIE xy chromaticity of a blackbody at `kelvin`, using the cubic spline
+/// approximation of the Planckian locus by Kim et al. (valid from 1667 K to 25000 K).
+pub fn kelvin_to_xy(kelvin: f32) -> [f32; 2] {
+    let t = f64::from(kelvin.clamp(1667.0, 25000.0));
+    let (t2, t3) = (t * t, t * t * t);
+    let x = if t <= 4000.0 {
+        -0.266_123_9e9 / t3 - 0.234_358_9e6 / t2 + 0.877_695_6e3 / t + 0.179_910
+    } else {
+        -3.025_846_9e9 / t3 + 2.107_037_9e6 / t2 + 0.222_634_7e3 / t + 0.240_390
+    };
+    let (x2, x3) = (x * x, x * x * x);
+    let y = if t <= 2222.0 {
+        -1.106_381_4 * x3 - 1.348_110_20 * x2 + 2.185_558_32 * x - 0.202_196_83
+    } else if t <= 4000.0 {
+        -0.954_947_6 * x3 - 1.374_185_93 * x2 + 2.091_370_15 * x - 0.167_488_67
+    } else {
+        3.081_758_0 * x3 - 5.873_386_70 * x2 + 3.751_129_97 * x - 0.370_014_83
+    };
+    [x as f32, y as f32]
+}
+
+/// LMS cone response of the white point at `kelvin`, with `tint` moving it along
+/// the green-magenta axis (CIE 1960 v) in steps of 0.02 per unit.
+pub fn white_lms(kelvin: f32, tint: f32) -> [f32; 3] {
+    let [x, y] = kelvin_to_xy(kelvin);
+    // xy -> CIE 1960 uv, shift v, and back.
+    let d = -2.0 * x + 12.0 * y + 3.0;
+    let u = 4.0 * x / d;
+    let v = 6.0 * y / d + tint * 0.02;
+    let d = 2.0 * u - 8.0 * v + 4.0;
+    let (x, y) = (3.0 * u / d, 2.0 * v / d);
+    mul_vec(&XYZ_TO_LMS, [x / y, 1.0, (1.0 - x - y) / y])
+}
+
+/// Linear Rec.709 matrix that makes the white at `kelvin` and `tint` neutral, with a
+/// von Kries adaptation in LMS. Lower temperatures make the image cooler, positive tint
+/// makes it more magenta.
+pub fn white_balance(kelvin: f32, tint: f32) -> Mat3 {
+    let src = white_lms(kelvin, tint);
+    let dst = white_lms(NEUTRAL_TEMPERATURE, 0.0);
+    let mut scale = IDENTITY;
+    for i in 0..3 {
+        scale[i][i] = dst[i] / src[i];
+    }
+    let to_lms = mul(&XYZ_TO_LMS, &REC709_TO_XYZ);
+    let from_lms = mul(&XYZ_TO_REC709, &LMS_TO_XYZ);
+    mul(&from_lms, &mul(&scale, &to_lms))
+}
+
+/// ASC Color Decision List slope/offset/power and saturation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cdl {
+    pub slope: [f32; 3],
+    pub offset: [f32; 3],
+    pub power: [f32; 3],
+    pub saturation: f32,
+}
+
+impl Default for Cdl {
+    fn default() -> Self {
+        Self {
+            slope: [1.0; 3],
+            offset: [0.0; 3],
+            power: [1.0; 3],
+            saturation: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CdlError(pub String);
+
+impl fmt::Display for CdlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CdlError {}
+
+/// Text between `<name ...>` and `</name>`, skipping elements whose name merely starts with `name`.
+fn element<'a>(src: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{name}");
+    let mut from = 0;
+    while let Some(pos) = src[from..].find(&open) {
+        let start = from + pos + open.len();
+        from = start;
+        let rest = &src[start..];
+        if !rest.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            continue;
+        }
+        let tag_end = rest.find('>')?;
+        if rest[..tag_end].ends_with('/') {
+            return Some("");
+        }
+        let body = &rest[tag_end + 1..];
+        return body.find(&format!("</{name}>")).map(|end| &body[..end]);
+    }
+    None
+}
+
+fn parse_values<const N: usize>(name: &str, text: &str) -> Result<[f32; N], CdlError> {
+    let values: Vec<&str> = text.split_whitespace().collect();
+    if values.len() != N {
+        return Err(CdlError(format!(
+            "{name} expects {N} values, got {}",
+            values.len()
+        )));
+    }
+    let mut out = [0.0; N];
+    for (o, v) in out.iter_mut().zip(values) {
+        *o = match v.parse::<f32>() {
+            Ok(v) if v.is_finite() => v,
+            _ => return Err(CdlError(format!("invalid number '{v}' in {name}"))),
+        };
+    }
+    Ok(out)
+}
+
+fn strip_comments(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(start) = rest.find("<!--") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start..].find("-->") {
+            Some(end) => &rest[start + end + 3..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+impl Cdl {
+    /// Parses the first `ColorCorrection` of a `.cc`, `.ccc` or `.cdl` file.
+    pub fn parse(src: &str) -> Result<Self, CdlError> {
+        let src = strip_comments(src);
+        let cc = element(&src, "ColorCorrection")
+            .ok_or_else(|| CdlError("missing ColorCorrection element".into()))?;
+        let sop = element(cc, "SOPNode");
+        // ASC CDL 1.01 spells it SATNode, later versions SatNode.
+        let sat = element(cc, "SatNode").or_else(|| element(cc, "SATNode"));
+        if sop.is_none() && sat.is_none() {
+            return Err(CdlError("ColorCorrection has no SOPNode or SatNode".into()));
+        }
+
+        let mut cdl = Cdl::default();
+        if let Some(sop) = sop {
+            if let Some(text) = element(sop, "Slope") {
+                cdl.slope = parse_values("Slope", text)?;
+            }
+            if let Some(text) = element(sop, "Offset") {
+                cdl.offset = parse_values("Offset", text)?;
+            }
+            if let Some(text) = element(sop, "Power") {
+                cdl.power = parse_values("Power", text)?;
+            }
+        }
+        if let Some(text) = sat.and_then(|sat| element(sat, "Saturation")) {
+            [cdl.saturation] = parse_values("Saturation", text)?;
+        }
+        if cdl.power.iter().any(|&p| p <= 0.0) {
+            return Err(CdlError("Power must be positive".into()));
+        }
+        Ok(cdl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::post_effect::test_util::assert_close;
+
+    #[test]
+    fn planckian_locus() {
+        // Reference chromaticities of the Planckian locus.
+        let [x, y] = kelvin_to_xy(2856.0);
+        assert_close(x, 0.4476, 2e-3);
+        assert_close(y, 0.4074, 2e-3);
+        let [x, y] = kelvin_to_xy(6504.0);
+        assert_close(x, 0.3135, 2e-3);
+        assert_close(y, 0.3236, 2e-3);
+    }
+
+    #[test]
+    fn white_balance_neutral_is_identity() {
+        let m = white_balance(NEUTRAL_TEMPERATURE, 0.0);
+        for (r, row) in m.iter().enumerate() {
+            for (c, &v) in row.iter().enumerate() {
+                assert_close(v, IDENTITY[r][c], 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn white_balance_direction() {
+        let white = [1.0; 3];
+        let [r, _, b] = mul_vec(&white_balance(3200.0, 0.0), white);
+        assert!(b > r, "low temperatures should cool the image");
+        let [r, _, b] = mul_vec(&white_balance(10000.0, 0.0), white);
+        assert!(r > b, "high temperatures should warm the image");
+        let [r, g, b] = mul_vec(&white_balance(NEUTRAL_TEMPERATURE, 1.0), white);
+        assert!(g < r && g < b, "positive tint should add magenta");
+    }
+
+    #[test]
+    fn white_balance_neutralizes_source_white() {
+        // Both whites have the same luminance, the adapted source white must
+        // map to the neutral white's LMS response.
+        let src = white_lms(3000.0, 0.5);
+        let dst = white_lms(NEUTRAL_TEMPERATURE, 0.0);
+        let m = mul(&XYZ_TO_LMS, &REC709_TO_XYZ);
+        let m_inv = mul(&XYZ_TO_REC709, &LMS_TO_XYZ);
+        let src_rgb = mul_vec(&m_inv, src);
+        let adapted = mul_vec(&m, mul_vec(&white_balance(3000.0, 0.5), src_rgb));
+        for i in 0..3 {
+            assert_close(adapted[i], dst[i], 1e-3);
+        }
+    }
+
+    #[test]
+    fn parses_cc() {
+        let src = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ColorCorrection id="shot_010">
+  <!-- <Slope>9 9 9</Slope> -->
+  <SOPNode>
+    <Description>warm</Description>
+    <Slope>1.1 1.0 0.9</Slope>
+    <Offset>0.01 0 -0.02</Offset>
+    <Power>1.0 1.05 1.1</Power>
+  </SOPNode>
+  <SatNode>
+    <Saturation>0.8</Saturation>
+  </SatNode>
+</ColorCorrection>"#;
+        let cdl = Cdl::parse(src).unwrap();
+        assert_eq!(cdl.slope, [1.1, 1.0, 0.9]);
+        assert_eq!(cdl.offset, [0.01, 0.0, -0.02]);
+        assert_eq!(cdl.power, [1.0, 1.05, 1.1]);
+        assert_eq!(cdl.saturation, 0.8);
+    }
+
+    #[test]
+    fn parses_first_decision_of_cdl() {
+        let src = r#"<ColorDecisionList xmlns="urn:ASC:CDL:v1.01">
+  <ColorDecision>
+    <ColorCorrection id="a">
+      <SOPNode><Slope>2 2 2</Slope><Offset>0 0 0</Offset><Power>1 1 1</Power></SOPNode>
+      <SATNode><Saturation>1.2</Saturation></SATNode>
+    </ColorCorrection>
+  </ColorDecision>
+  <ColorDecision>
+    <ColorCorrection id="b">
+      <SOPNode><Slope>3 3 3</Slope></SOPNode>
+    </ColorCorrection>
+  </ColorDecision>
+</ColorDecisionList>"#;
+        let cdl = Cdl::parse(src).unwrap();
+        assert_eq!(cdl.slope, [2.0; 3]);
+        assert_eq!(cdl.saturation, 1.2);
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(Cdl::parse("<ColorCorrectionCollection/>").is_err());
+        assert!(Cdl::parse("<ColorCorrection id=\"x\"></ColorCorrection>").is_err());
+        let e =
+            Cdl::parse("<ColorCorrection><SOPNode><Slope>1 1</Slope></SOPNode></ColorCorrection>")
+                .unwrap_err();
+        assert_eq!(e.to_string(), "Slope expects 3 values, got 2");
+        let e = Cdl::parse(
+            "<ColorCorrection><SatNode><Saturation>x</Saturation></SatNode></ColorCorrection>",
+        )
+        .unwrap_err();
+        assert_eq!(e.to_string(), "invalid number 'x' in Saturation");
+        assert!(
+            Cdl::parse(
+                "<ColorCorrection><SOPNode><Power>1 0 1</Power></SOPNode></ColorCorrection>"
+            )
+            .is_err()
+        );
+    }
+}
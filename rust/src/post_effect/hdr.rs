@@ -0,0 +1,142 @@
+//! HDR output encodings. `linear_to_pq` and `REC709_TO_REC2020` in `tonemap.glsl` are ports of
+//! `pq_encode` and `rec709_to_rec2020`, the tests check that their constants match.
+
+/// Luminance of scRGB 1.0.
+pub const SCRGB_WHITE_NITS: f32 = 80.0;
+/// Luminance of PQ 1.0.
+pub const PQ_MAX_NITS: f32 = 10000.0;
+
+// SMPTE ST 2084 constants.
+const PQ_M1: f32 = 2610.0 / 16384.0;
+const PQ_M2: f32 = 2523.0 / 4096.0 * 128.0;
+const PQ_C1: f32 = 3424.0 / 4096.0;
+const PQ_C2: f32 = 2413.0 / 4096.0 * 32.0;
+const PQ_C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+/// BT.2087 linear Rec.709 to linear Rec.2020, by rows.
+const REC709_TO_REC2020: [[f32; 3]; 3] = [
+    [0.627404, 0.329283, 0.043313],
+    [0.069097, 0.919540, 0.011362],
+    [0.016391, 0.088013, 0.895595],
+];
+
+/// PQ inverse EOTF, absolute luminance in nits to a signal in 0..1.
+#[allow(dead_code, reason = "encoded on the GPU")]
+pub fn pq_encode(nits: f32) -> f32 {
+    let y = (nits / PQ_MAX_NITS).clamp(0.0, 1.0).powf(PQ_M1);
+    ((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y)).powf(PQ_M2)
+}
+
+/// PQ EOTF, a signal in 0..1 to absolute luminance in nits.
+#[allow(dead_code, reason = "encoded on the GPU")]
+pub fn pq_decode(signal: f32) -> f32 {
+    let e = signal.clamp(0.0, 1.0).powf(1.0 / PQ_M2);
+    let y = ((e - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * e)).powf(1.0 / PQ_M1);
+    y * PQ_MAX_NITS
+}
+
+#[allow(dead_code, reason = "converted on the GPU")]
+pub fn rec709_to_rec2020(color: [f32; 3]) -> [f32; 3] {
+    REC709_TO_REC2020.map(|row| row.iter().zip(color).map(|(m, c)| m * c).sum())
+}
+
+/// Brightest value the tonemappers output, relative to paper white at 1.0. Never below
+/// SDR.
+pub fn display_peak(paper_white_nits: f32, max_nits: f32) -> f32 {
+    (max_nits / paper_white_nits.max(1.0)).max(1.0)
+}
+
+/// Scale from paper white at 1.0 to scRGB, where 1.0 is 80 nits.
+pub fn scrgb_scale(paper_white_nits: f32) -> f32 {
+    paper_white_nits / SCRGB_WHITE_NITS
+}
+
+/// Scale from paper white at 1.0 to the 0..1 luminance range of PQ.
+pub fn pq_scale(paper_white_nits: f32) -> f32 {
+    paper_white_nits / PQ_MAX_NITS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::post_effect::test_util::assert_close;
+
+    const TONEMAP_SHADER: &str = include_str!("../../../godot/glsl/tonemap.glsl");
+
+    /// Numbers in the initializer of the shader constant `name`.
+    fn shader_const(name: &str) -> Vec<f32> {
+        let start = TONEMAP_SHADER
+            .find(&format!(" {name} = "))
+            .unwrap_or_else(|| panic!("{name} not found in tonemap.glsl"));
+        let init = &TONEMAP_SHADER[start..];
+        init[..init.find(';').unwrap()]
+            .split(|c: char| c == ',' || c == '(' || c == ')' || c.is_whitespace())
+            .filter_map(|token| token.parse().ok())
+            .collect()
+    }
+
+    #[test]
+    fn shader_constants_match() {
+        for (name, value) in [
+            ("PQ_M1", PQ_M1),
+            ("PQ_M2", PQ_M2),
+            ("PQ_C1", PQ_C1),
+            ("PQ_C2", PQ_C2),
+            ("PQ_C3", PQ_C3),
+        ] {
+            assert_eq!(shader_const(name), [value], "{name}");
+        }
+        assert_eq!(
+            shader_const("REC709_TO_REC2020"),
+            REC709_TO_REC2020.as_flattened()
+        );
+    }
+
+    #[test]
+    fn pq_reference_values() {
+        assert_eq!(pq_encode(0.0), PQ_C1.powf(PQ_M2));
+        assert_close(pq_encode(PQ_MAX_NITS), 1.0, 1e-6);
+        // Reference code values of BT.2100.
+        assert_close(pq_encode(100.0), 0.5081, 1e-4);
+        assert_close(pq_encode(1000.0), 0.7518, 1e-4);
+        assert_close(pq_encode(203.0), 0.5806, 1e-4);
+    }
+
+    #[test]
+    fn pq_round_trip() {
+        for nits in [0.01, 0.1, 1.0, 80.0, 203.0, 1000.0, 4000.0, 10000.0] {
+            assert_close(pq_decode(pq_encode(nits)), nits, nits * 1e-3);
+        }
+        assert_eq!(pq_decode(0.0), 0.0);
+        assert!(pq_encode(2.0 * PQ_MAX_NITS) <= 1.0);
+    }
+
+    #[test]
+    fn pq_is_monotonic() {
+        let mut last = pq_encode(0.0);
+        for i in 1..=1000 {
+            let v = pq_encode(i as f32 * 10.0);
+            assert!(v > last);
+            last = v;
+        }
+    }
+
+    #[test]
+    fn gamut_conversion_keeps_white() {
+        for c in rec709_to_rec2020([1.0; 3]) {
+            assert_close(c, 1.0, 1e-5);
+        }
+        // Rec.709 primaries are inside Rec.2020.
+        for primary in [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]] {
+            assert!(rec709_to_rec2020(primary).iter().all(|&c| c > 0.0));
+        }
+    }
+
+    #[test]
+    fn scales() {
+        assert_eq!(display_peak(200.0, 1000.0), 5.0);
+        assert_eq!(display_peak(400.0, 300.0), 1.0);
+        assert_eq!(scrgb_scale(80.0), 1.0);
+        assert_eq!(pq_scale(PQ_MAX_NITS), 1.0);
+    }
+}
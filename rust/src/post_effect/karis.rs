@@ -0,0 +1,139 @@
+//! Karis average used by the first pass of `blur_downsample.glsl`. `BlurDownsample` uploads
+//! the suppression strength, the average itself is a CPU reference of the shader for the
+//! tests.
+
+/// Luminance weights of the Karis average, `KARIS_LUMINANCE_WEIGHTS` in the shader.
+#[cfg(test)]
+const LUMINANCE_WEIGHTS: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// `firefly_suppression` as uploaded: blends from a plain average (0.0) to Karis'
+/// `1 / (1 + luma)` weighting (1.0), which keeps single very bright pixels from dominating
+/// the bloom.
+pub fn suppression_strength(suppression: f32) -> f32 {
+    suppression.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+fn luminance(color: [f32; 3]) -> f32 {
+    color
+        .iter()
+        .zip(LUMINANCE_WEIGHTS)
+        .map(|(c, w)| c * w)
+        .sum()
+}
+
+/// Inputs of `karis_weight` in the shader, besides the sample.
+#[cfg(test)]
+#[derive(Clone, Copy)]
+struct KarisParams {
+    luminance_multiplier: f32,
+    exposure: f32,
+    suppression: f32,
+}
+
+/// Weight of a sample in the average, `karis_weight` in the shader. The luma is taken after
+/// the luminance multiplier and the glow exposure, like the thresholding of the first pass.
+#[cfg(test)]
+fn karis_weight(color: [f32; 3], params: KarisParams) -> f32 {
+    let scale = params.luminance_multiplier * params.exposure;
+    let luma = luminance(color.map(|c| c * scale));
+    1.0 / (1.0 + luma.max(0.0) * suppression_strength(params.suppression))
+}
+
+/// Weighted average of `samples`, as computed per output texel on the GPU.
+#[cfg(test)]
+fn karis_average(samples: &[[f32; 3]], params: KarisParams) -> [f32; 3] {
+    let mut sum = [0.0; 3];
+    let mut weight_sum = 0.0;
+    for &sample in samples {
+        let weight = karis_weight(sample, params);
+        for (s, c) in sum.iter_mut().zip(sample) {
+            *s += c * weight;
+        }
+        weight_sum += weight;
+    }
+    if weight_sum > 0.0 {
+        sum.map(|s| s / weight_sum)
+    } else {
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::post_effect::test_util::assert_close;
+
+    /// Luminance multiplier of the first downsample pass.
+    const FIRST_PASS_MULTIPLIER: f32 = 2.0;
+
+    fn params(exposure: f32, suppression: f32) -> KarisParams {
+        KarisParams {
+            luminance_multiplier: FIRST_PASS_MULTIPLIER,
+            exposure,
+            suppression,
+        }
+    }
+
+    #[test]
+    fn no_suppression_is_plain_average() {
+        let samples = [[0.0; 3], [1.0, 2.0, 3.0], [4.0; 3], [100.0, 0.0, 0.0]];
+        let avg = karis_average(&samples, params(0.7, 0.0));
+        assert_close(avg[0], 26.25, 1e-4);
+        assert_close(avg[1], 1.5, 1e-4);
+        assert_close(avg[2], 1.75, 1e-4);
+    }
+
+    #[test]
+    fn uniform_samples_are_unchanged() {
+        let samples = [[2.0, 1.0, 0.5]; 4];
+        let avg = karis_average(&samples, params(0.7, 1.0));
+        for (a, b) in avg.iter().zip(samples[0]) {
+            assert_close(*a, b, 1e-5);
+        }
+    }
+
+    #[test]
+    fn fireflies_are_suppressed() {
+        let samples = [[0.1; 3], [0.1; 3], [0.1; 3], [1000.0; 3]];
+        let plain = karis_average(&samples, params(0.7, 0.0))[0];
+        let half = karis_average(&samples, params(0.7, 0.5))[0];
+        let full = karis_average(&samples, params(0.7, 1.0))[0];
+        assert!(plain > half && half > full);
+        // The firefly contributes at most about 1 / weight_sum of the dark samples.
+        assert!(full < 1.5, "{full}");
+    }
+
+    #[test]
+    fn suppression_is_clamped() {
+        assert_eq!(suppression_strength(-1.0), 0.0);
+        assert_eq!(suppression_strength(2.0), 1.0);
+        assert_eq!(
+            karis_weight([1.0; 3], params(0.7, 4.0)),
+            karis_weight([1.0; 3], params(0.7, 1.0))
+        );
+    }
+
+    #[test]
+    fn weights() {
+        assert_eq!(karis_weight([10.0; 3], params(0.7, 0.0)), 1.0);
+        // Luma 1.0 after the multiplier and the exposure.
+        assert_close(karis_weight([1.0; 3], params(0.5, 1.0)), 0.5, 1e-5);
+        // 0.25 * 2.0 * 1.5 = 0.75.
+        assert_close(karis_weight([0.25; 3], params(1.5, 1.0)), 1.0 / 1.75, 1e-5);
+        assert!(
+            karis_weight([1.0; 3], params(0.7, 1.0)) > karis_weight([2.0; 3], params(0.7, 1.0))
+        );
+        // Negative colors must not produce weights above 1.
+        assert_eq!(karis_weight([-1.0; 3], params(0.7, 1.0)), 1.0);
+    }
+
+    #[test]
+    fn exposure_scales_the_suppression() {
+        // The same sample counts as a brighter firefly at a higher exposure.
+        let dim = karis_weight([1.0; 3], params(0.25, 1.0));
+        let bright = karis_weight([1.0; 3], params(4.0, 1.0));
+        assert_close(dim, 1.0 / 1.5, 1e-5);
+        assert_close(bright, 1.0 / 9.0, 1e-5);
+    }
+}
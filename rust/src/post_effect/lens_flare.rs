@@ -0,0 +1,159 @@
+use godot::{
+    classes::{
+        Engine, RdPipelineSpecializationConstant, RdUniform, UniformSetCacheRd,
+        rendering_device::UniformType,
+    },
+    prelude::*,
+};
+use zerocopy::FromBytes;
+
+use crate::{GlobalRidsSingleton, post_effect::copy::Raster};
+
+const LENS_FLARE_SHADER_PATH: &str = "uid://ccvxwymr3bj4e";
+
+#[derive(
+    Debug,
+    zerocopy::FromBytes,
+    zerocopy::IntoBytes,
+    zerocopy::Immutable,
+    zerocopy::KnownLayout,
+    Default,
+)]
+#[repr(C)]
+struct LensFlarePushConstants {
+    source_pixel_size_x: f32, // 04 - 04
+    source_pixel_size_y: f32, // 04 - 08
+    ghost_dispersal: f32,     // 04 - 12
+    halo_width: f32,          // 04 - 16
+
+    ghost_intensity: f32,      // 04 - 20
+    halo_intensity: f32,       // 04 - 24
+    chromatic_distortion: f32, // 04 - 28
+    starburst_intensity: f32,  // 04 - 32
+
+    starburst_rotation: f32, // 04 - 36
+    aspect: f32,             // 04 - 40
+    ghost_count: i32,        // 04 - 44
+    pad: f32,                // 04 - 48
+}
+
+pub struct LensFlareSettings {
+    pub ghost_count: i32,
+    /// Spacing of the ghosts along the optical axis, as a fraction of the distance to the center.
+    pub ghost_dispersal: f32,
+    pub ghost_intensity: f32,
+    /// Color of the ghosts from the center (left) to the corners (right), white if invalid.
+    pub ghost_gradient_tex: Rid,
+    /// Halo radius in uv units.
+    pub halo_width: f32,
+    pub halo_intensity: f32,
+    /// Offset of the red and blue channels in source pixels.
+    pub chromatic_distortion: f32,
+    pub starburst_intensity: f32,
+    /// Radians, derived from the camera orientation.
+    pub starburst_rotation: f32,
+}
+
+/// Screen-space lens flare generated from the thresholded first glow downsample.
+pub struct LensFlare {
+    renderer: Raster,
+    scs: Array<Gd<RdPipelineSpecializationConstant>>,
+    ubo: PackedArray<u8>,
+    uniforms: Array<Gd<RdUniform>>,
+    sampler: Rid,
+    default_tex_white: Rid,
+}
+
+impl LensFlare {
+    pub fn init() -> Self {
+        let ubo_bytes: [u8; std::mem::size_of::<LensFlarePushConstants>()] =
+            zerocopy::transmute!(LensFlarePushConstants::default());
+        let ubo = PackedArray::<u8>::from(&ubo_bytes);
+
+        let mut uniforms = Array::new();
+        let mut uniform_src_tex = RdUniform::new_gd();
+        uniform_src_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_src_tex.set_binding(0);
+        uniforms.push(&uniform_src_tex);
+        let mut uniform_ghost_gradient_tex = RdUniform::new_gd();
+        uniform_ghost_gradient_tex.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform_ghost_gradient_tex.set_binding(1);
+        uniforms.push(&uniform_ghost_gradient_tex);
+
+        let singleton = Engine::singleton()
+            .get_singleton(&GlobalRidsSingleton::class_name().to_string_name())
+            .unwrap()
+            .cast::<GlobalRidsSingleton>();
+        let sampler = singleton.bind().default_sampler;
+        let default_tex_white = singleton.bind().default_texture_white;
+
+        Self {
+            renderer: Raster::load_shader_file_path(LENS_FLARE_SHADER_PATH),
+            scs: Array::new(),
+            ubo,
+            uniforms,
+            sampler,
+            default_tex_white,
+        }
+    }
+
+    pub fn exec(
+        &mut self,
+        source_rd_texture: Rid,
+        dest_texture: Rid,
+        size: Vector2i,
+        settings: LensFlareSettings,
+    ) {
+        // Pipeline.
+        self.renderer.setup_pipeline_texure(dest_texture, &self.scs);
+        // UBO.
+        let ubo = self.ubo.as_mut_slice();
+        let ubo_mut = LensFlarePushConstants::mut_from_bytes(ubo).unwrap();
+        ubo_mut.source_pixel_size_x = 1.0 / size.x as f32;
+        ubo_mut.source_pixel_size_y = 1.0 / size.y as f32;
+        ubo_mut.ghost_dispersal = settings.ghost_dispersal;
+        ubo_mut.halo_width = settings.halo_width;
+        ubo_mut.ghost_intensity = settings.ghost_intensity;
+        ubo_mut.halo_intensity = settings.halo_intensity;
+        ubo_mut.chromatic_distortion = settings.chromatic_distortion;
+        ubo_mut.starburst_intensity = settings.starburst_intensity;
+        ubo_mut.starburst_rotation = settings.starburst_rotation;
+        ubo_mut.aspect = size.x as f32 / size.y as f32;
+        ubo_mut.ghost_count = settings.ghost_count.max(0);
+
+        let mut uniform_src_tex = self.uniforms.get(0).unwrap();
+        uniform_src_tex.clear_ids();
+        uniform_src_tex.add_id(self.sampler);
+        uniform_src_tex.add_id(source_rd_texture);
+
+        let mut uniform_ghost_gradient_tex = self.uniforms.get(1).unwrap();
+        uniform_ghost_gradient_tex.clear_ids();
+        uniform_ghost_gradient_tex.add_id(self.sampler);
+        if settings.ghost_gradient_tex.is_valid() {
+            uniform_ghost_gradient_tex.add_id(settings.ghost_gradient_tex);
+        } else {
+            uniform_ghost_gradient_tex.add_id(self.default_tex_white);
+        }
+
+        let uniform_set0 = UniformSetCacheRd::get_cache(self.renderer.shader, 0, &self.uniforms);
+
+        let draw_list = self.renderer.rd.draw_list_begin(self.renderer.framebuffer);
+        self.renderer
+            .rd
+            .draw_list_bind_render_pipeline(draw_list, self.renderer.pipeline);
+        self.renderer.rd.draw_list_set_push_constant(
+            draw_list,
+            &self.ubo,
+            self.ubo.len().try_into().unwrap(),
+        );
+        self.renderer
+            .rd
+            .draw_list_bind_uniform_set(draw_list, uniform_set0, 0);
+        self.renderer
+            .rd
+            .draw_list_draw_ex(draw_list, false, 1)
+            .procedural_vertex_count(3)
+            .done();
+        self.renderer.rd.draw_list_end();
+    }
+}
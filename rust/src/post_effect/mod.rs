@@ -1,28 +1,77 @@
+pub mod auto_exposure;
+pub mod camera_exposure;
+pub mod cas;
 pub mod copy;
+pub mod cube_lut;
+pub mod grading;
+pub mod hdr;
+pub mod karis;
+pub mod lens_flare;
+pub mod smaa;
+pub mod smaa_tables;
+#[cfg(test)]
+mod test_util;
 
-use std::sync::LazyLock;
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
 
 use godot::{
     classes::{
-        CompositorEffect, Engine, FramebufferCacheRd, ICompositorEffect, RenderData,
-        RenderSceneBuffersRd, RenderingDevice, RenderingServer, Texture2D,
+        CameraAttributes, CameraAttributesPhysical, CompositorEffect, Curve, Engine, FileAccess,
+        FramebufferCacheRd, ICompositorEffect, ProjectSettings, RdTextureFormat, RdTextureView,
+        RenderData, RenderSceneBuffersRd, RenderingDevice, RenderingServer, SceneTree, Texture,
+        Texture2D, Time, Viewport,
         compositor_effect::EffectCallbackType,
-        rendering_device::{TextureSamples, TextureUsageBits},
+        object::PropertyUsageFlags,
+        rendering_device::{DataFormat, TextureSamples, TextureType, TextureUsageBits},
     },
+    meta::PropertyInfo,
     prelude::*,
 };
+use zerocopy::IntoBytes;
 
 use crate::{
     GlobalRidsSingleton,
-    post_effect::copy::{
-        BlurDownsample, BlurUpsample, GlowMode, TexCopy, ToneMapper, ToneMapperType,
+    post_effect::{
+        auto_exposure::{
+            AutoExposureSettings, ExposureMode, LuminanceHistogram, adapt_ev, exposure_from_ev,
+            histogram_average_ev,
+        },
+        camera_exposure::{ev100, relative_exposure},
+        cas::Cas,
+        copy::{
+            AgxLook, AgxLookParams, BlurDownsample, BlurUpsample, ChromaticAberrationSettings,
+            FxaaQuality, FxaaSettings, GlowMode, GlowThreshold, GlowThresholdMode, GradingSettings,
+            GradingSpace, GrainSettings, HdrOutputSettings, LensDistortionSettings, OutputMode,
+            STREAK_PASSES, Streak, TexCopy, ToneMapMode, ToneMapper, ToneMapperType,
+            VignetteSettings, hable_curve, lottes_coefficients,
+        },
+        cube_lut::CubeLut,
+        grading::{Cdl, NEUTRAL_TEMPERATURE, white_balance},
+        lens_flare::{LensFlare, LensFlareSettings},
+        smaa::{AntiAliasing, Smaa},
     },
 };
 
+const CUSTOM_CURVE_SIZE: usize = 256;
+
+/// Upper bound of `glow_levels`, more than a 32768 px buffer has mips for.
+const MAX_GLOW_LEVELS: usize = 16;
+
 static RB_SCOPE_BUFFERS: LazyLock<StringName> =
     LazyLock::new(|| StringName::from(c"my_render_buffers"));
 static RB_TEX_BLUR_0: LazyLock<StringName> = LazyLock::new(|| StringName::from(c"blur_0"));
 static RB_TEX_BLUR_1: LazyLock<StringName> = LazyLock::new(|| StringName::from(c"blur_1"));
+static RB_TEX_STREAK_0: LazyLock<StringName> = LazyLock::new(|| StringName::from(c"streak_0"));
+static RB_TEX_STREAK_1: LazyLock<StringName> = LazyLock::new(|| StringName::from(c"streak_1"));
+static RB_TEX_LENS_FLARE: LazyLock<StringName> = LazyLock::new(|| StringName::from(c"lens_flare"));
+static RB_TEX_SHARPEN: LazyLock<StringName> = LazyLock::new(|| StringName::from(c"sharpen"));
+static RB_TEX_SMAA_SOURCE: LazyLock<StringName> =
+    LazyLock::new(|| StringName::from(c"smaa_source"));
+static RB_TEX_SMAA_EDGES: LazyLock<StringName> = LazyLock::new(|| StringName::from(c"smaa_edges"));
+static RB_TEX_SMAA_BLEND: LazyLock<StringName> = LazyLock::new(|| StringName::from(c"smaa_blend"));
 
 #[derive(GodotClass)]
 #[class(base=CompositorEffect,tool)]
@@ -31,14 +80,63 @@ pub struct PostEffectToneMap {
     rd: Gd<RenderingDevice>,
     downsample: BlurDownsample,
     upsample: BlurUpsample,
+    streak: Streak,
+    lens_flare: LensFlare,
+    cas: Cas,
+    smaa: Smaa,
     tonemapper: ToneMapper,
     copy: TexCopy,
+    luminance_histogram: LuminanceHistogram,
     global_rids_singleton: Gd<GlobalRidsSingleton>,
 
+    /// FXAA runs inside the tonemap pass, SMAA in three passes after it. SMAA only detects
+    /// orthogonal edges, High and Ultra lack the diagonal detection of the reference presets.
+    #[var(get, set = set_anti_aliasing)]
+    #[export]
+    anti_aliasing: AntiAliasing,
+    /// Lower presets search edges in fewer, larger steps and are faster.
+    #[export]
+    fxaa_quality: FxaaQuality,
+    /// Amount of subpixel aliasing removal, 0.0 keeps the image sharper.
+    #[export(range = (0.0, 1.0))]
+    fxaa_subpixel_quality: f32,
+    /// Local contrast needed to apply FXAA, relative to the brightest neighbor.
+    #[export(range = (0.063, 0.333))]
+    fxaa_edge_threshold: f32,
+    /// Absolute local contrast needed to apply FXAA, trims dark areas.
+    #[export(range = (0.0, 0.0833))]
+    fxaa_edge_threshold_min: f32,
+    /// Dithers the output to hide banding, scaled to the bit depth of the color buffer. Not
+    /// applied to scRGB output.
     #[export]
-    use_fxaa: bool,
+    use_debanding: bool,
+    /// Encoding of the output. The HDR modes expect the viewport to output HDR.
+    #[var(get, set = set_output_mode)]
     #[export]
+    output_mode: OutputMode,
+    /// Luminance SDR white is displayed at.
+    #[export(range = (80.0, 500.0, or_greater))]
+    output_paper_white_nits: f32,
+    /// Peak luminance of the display. Only Reinhard, GT, Lottes and PBR Neutral extend to it,
+    /// the other tonemappers peak at paper white.
+    #[export(range = (100.0, 10000.0))]
+    output_max_nits: f32,
+    /// Contrast adaptive sharpening of the tonemapped image, 0.0 disables it.
+    #[export(range = (0.0, 1.0))]
+    sharpen_intensity: f32,
+    /// With FXAA enabled, sharpen inside FXAA where it finds no edge instead of running a
+    /// separate pass, so that antialiased edges don't get sharpened again.
+    #[export]
+    sharpen_with_fxaa: bool,
+    /// Intensity of each glow level. Level `i` is blurred at 1/2^(i+2) of the render
+    /// resolution: it is downsampled into mip `i + 1` of `blur_1` and upsampled into mip
+    /// `i + 2` of `blur_0`. Levels beyond the mip count of the viewport are ignored.
+    #[export]
+    #[var(get, set = set_glow_levels)]
     glow_levels: PackedArray<f32>,
+    /// Optional tint of each glow level, levels without a color stay white.
+    #[export]
+    glow_level_tints: PackedArray<Color>,
     #[export]
     glow_intensity: f32,
     #[export]
@@ -49,22 +147,867 @@ pub struct PostEffectToneMap {
     glow_bloom: f32,
     #[export]
     glow_blend_mode: GlowMode,
+    #[var(get, set = set_glow_threshold_mode)]
+    #[export]
+    glow_threshold_mode: GlowThresholdMode,
     #[export]
     glow_hdr_bleed_threshold: f32,
     #[export]
     glow_hdr_bleed_scale: f32,
+    /// EV above an exposed value of 1.0 where glow starts, follows exposure changes.
+    #[export]
+    glow_threshold_ev: f32,
+    /// Width in EV of the soft transition below `glow_threshold_ev`, 0 is a hard cut.
+    #[export(range = (0.0, 4.0, or_greater))]
+    glow_threshold_knee_ev: f32,
     #[export]
     glow_hdr_luminance_cap: f32,
+    /// Weights the first downsample by `1 / (1 + luminance)` (Karis average), so that single
+    /// very bright pixels don't flicker in the glow. 0 is a plain average.
+    #[export(range = (0.0, 1.0))]
+    glow_firefly_suppression: f32,
     #[export]
     glow_map_strength: f32,
     #[export]
     glow_map: Option<Gd<Texture2D>>,
+    /// Dirt texture lit up by the glow of `lens_dirt_glow_level`.
+    #[export]
+    lens_dirt_texture: Option<Gd<Texture2D>>,
+    #[export]
+    lens_dirt_intensity: f32,
+    /// Glow level lighting the dirt, see `glow_levels`. Wide levels give a soft response.
+    #[export]
+    lens_dirt_glow_level: i32,
+    /// Anamorphic streaks from the thresholded first glow downsample, 0.0 disables them.
+    #[export]
+    streak_intensity: f32,
+    #[export]
+    streak_direction: Vector2,
+    /// Attenuation per quarter-resolution pixel, closer to 1.0 gives longer streaks.
+    #[export]
+    streak_length: f32,
+    #[export]
+    streak_tint: Color,
+    /// Ghosts and halo reflected through the screen center, 0.0 disables the flare.
+    #[export]
+    lens_flare_intensity: f32,
+    #[export]
+    lens_flare_ghost_count: i32,
+    #[export]
+    lens_flare_ghost_dispersal: f32,
+    /// Ghost color from the screen center (left) to the corners (right),
+    /// usually a `GradientTexture1D`.
+    #[export]
+    lens_flare_ghost_gradient: Option<Gd<Texture2D>>,
+    #[export]
+    lens_flare_halo_width: f32,
+    #[export]
+    lens_flare_halo_intensity: f32,
+    /// Offset of the red and blue channels, in quarter-resolution pixels.
+    #[export]
+    lens_flare_chromatic_distortion: f32,
+    #[export]
+    lens_flare_starburst_intensity: f32,
+    #[export]
+    exposure_mode: ExposureMode,
     #[export]
     exposure: f32,
     #[export]
+    auto_exposure_min_ev: f32,
+    #[export]
+    auto_exposure_max_ev: f32,
+    #[export]
+    auto_exposure_low_percent: f32,
+    #[export]
+    auto_exposure_high_percent: f32,
+    #[export]
+    auto_exposure_compensation: f32,
+    #[export]
+    auto_exposure_speed_up: f32,
+    #[export]
+    auto_exposure_speed_down: f32,
+    auto_exposure_target_ev: Option<f32>,
+    auto_exposure_ev: Option<f32>,
+    auto_exposure_last_usec: u64,
+    camera_attributes: Arc<Mutex<CameraAttributesLookup>>,
+    /// `rendering/lights_and_shadows/use_physical_light_units`, read once.
+    physical_light_units: bool,
+    #[export]
     white: f32,
     #[export]
+    #[var(get, set = set_tonemap_type)]
     tonemap_type: ToneMapperType,
+    #[export]
+    tonemap_mode: ToneMapMode,
+    /// Blend between per-channel tonemapping (0.0) and `tonemap_mode` (1.0).
+    #[export]
+    tonemap_mode_blend: f32,
+    /// Scales the log encoding around middle gray before the sigmoid.
+    #[export]
+    agx_contrast: f32,
+    #[export]
+    #[var(get, set = set_agx_look)]
+    agx_look: AgxLook,
+    #[export]
+    agx_look_offset: Vector3,
+    #[export]
+    agx_look_slope: Vector3,
+    #[export]
+    agx_look_power: Vector3,
+    #[export]
+    agx_look_saturation: f32,
+    /// Maximum display brightness.
+    #[export]
+    gt_max_brightness: f32,
+    #[export]
+    gt_contrast: f32,
+    #[export]
+    gt_linear_start: f32,
+    #[export]
+    gt_linear_length: f32,
+    /// Toe curvature.
+    #[export]
+    gt_black: f32,
+    /// Black level offset.
+    #[export]
+    gt_pedestal: f32,
+    #[export]
+    lottes_contrast: f32,
+    #[export]
+    lottes_shoulder: f32,
+    /// Input value mapped to display white.
+    #[export]
+    lottes_hdr_max: f32,
+    #[export]
+    lottes_mid_in: f32,
+    #[export]
+    lottes_mid_out: f32,
+    #[export]
+    hable_shoulder_strength: f32,
+    #[export]
+    hable_linear_strength: f32,
+    #[export]
+    hable_linear_angle: f32,
+    #[export]
+    hable_toe_strength: f32,
+    #[export]
+    hable_toe_numerator: f32,
+    #[export]
+    hable_toe_denominator: f32,
+    /// Input value mapped to display white.
+    #[export]
+    hable_white_point: f32,
+    /// Maps log2 of the input between `custom_curve_min_ev` and `custom_curve_max_ev`
+    /// to display-linear output.
+    #[export]
+    #[var(get, set = set_custom_curve)]
+    custom_curve: Option<Gd<Curve>>,
+    /// Optional per-channel curves applied to the output of `custom_curve`.
+    #[export]
+    #[var(get, set = set_custom_curve_r)]
+    custom_curve_r: Option<Gd<Curve>>,
+    #[export]
+    #[var(get, set = set_custom_curve_g)]
+    custom_curve_g: Option<Gd<Curve>>,
+    #[export]
+    #[var(get, set = set_custom_curve_b)]
+    custom_curve_b: Option<Gd<Curve>>,
+    #[export]
+    custom_curve_min_ev: f32,
+    #[export]
+    custom_curve_max_ev: f32,
+    custom_curve_tex: Rid,
+    custom_curve_dirty: bool,
+    /// `.cube` LUT applied after tonemapping, sampled with display-encoded (sRGB) input.
+    #[export(file = "*.cube")]
+    #[var(get, set = set_color_lut)]
+    color_lut: GString,
+    color_lut_tex: Rid,
+    color_lut_domain_min: [f32; 3],
+    color_lut_domain_max: [f32; 3],
+    /// Same as `Environment.adjustment_*`, applied after tonemapping.
+    #[export]
+    #[var(get, set = set_adjustment_enabled)]
+    adjustment_enabled: bool,
+    #[export]
+    adjustment_brightness: f32,
+    #[export]
+    adjustment_contrast: f32,
+    #[export]
+    adjustment_saturation: f32,
+    /// A `Texture2D` (usually a `GradientTexture1D`) or a `Texture3D`.
+    #[export]
+    adjustment_color_correction: Option<Gd<Texture>>,
+    #[export]
+    #[var(get, set = set_grading_enabled)]
+    grading_enabled: bool,
+    /// Space of lift/gamma/gain and the CDL, white balance and the channel mixer are always linear.
+    #[export]
+    grading_space: GradingSpace,
+    #[export]
+    grading_after_tonemap: bool,
+    /// Color temperature in Kelvin that becomes neutral, lower values cool the image.
+    #[export]
+    grading_temperature: f32,
+    /// Green (negative) to magenta (positive) shift.
+    #[export]
+    grading_tint: f32,
+    #[export]
+    grading_lift: Color,
+    #[export]
+    grading_gamma: Color,
+    #[export]
+    grading_gain: Color,
+    /// Contribution of the input channels to the output red channel.
+    #[export]
+    grading_channel_mixer_red: Vector3,
+    #[export]
+    grading_channel_mixer_green: Vector3,
+    #[export]
+    grading_channel_mixer_blue: Vector3,
+    /// ASC CDL `.cc`/`.cdl` file, loading it overwrites the CDL values below.
+    #[export(file = "*.cc,*.ccc,*.cdl")]
+    #[var(get, set = set_grading_cdl)]
+    grading_cdl: GString,
+    #[export]
+    grading_cdl_slope: Vector3,
+    #[export]
+    grading_cdl_offset: Vector3,
+    #[export]
+    grading_cdl_power: Vector3,
+    #[export]
+    grading_cdl_saturation: f32,
+    #[export]
+    #[var(get, set = set_vignette_enabled)]
+    vignette_enabled: bool,
+    #[export(range = (0.0, 1.0, or_greater))]
+    vignette_intensity: f32,
+    #[export(range = (0.01, 1.0))]
+    vignette_smoothness: f32,
+    /// 1 is a circle, 0 follows the screen rectangle.
+    #[export(range = (0.0, 1.0))]
+    vignette_roundness: f32,
+    /// Offset of the vignette center from the screen center, in uv units.
+    #[export]
+    vignette_center: Vector2,
+    /// Alpha fades the vignette out.
+    #[export]
+    vignette_color: Color,
+    /// Multiplies the falloff by its red channel, white leaves the image untouched.
+    #[export]
+    vignette_mask: Option<Gd<Texture2D>>,
+    /// Darken the tonemapped image (stylized) instead of the scene light (physical falloff).
+    #[export]
+    vignette_after_tonemap: bool,
+    #[export]
+    #[var(get, set = set_grain_enabled)]
+    grain_enabled: bool,
+    #[export(range = (0.0, 1.0, or_greater))]
+    grain_intensity: f32,
+    /// Size of a grain in pixels.
+    #[export(range = (1.0, 8.0, or_greater))]
+    grain_size: f32,
+    /// Fades the grain out towards the highlights, 0 applies it evenly.
+    #[export(range = (0.0, 1.0))]
+    grain_response: f32,
+    /// Optional tileable grain, the red channel replaces the procedural noise.
+    #[export]
+    grain_texture: Option<Gd<Texture2D>>,
+    /// Radial color fringing towards the borders, 0.0 disables it.
+    #[export(range = (0.0, 1.0, or_greater))]
+    chromatic_aberration_intensity: f32,
+    #[export(range = (3.0, 32.0))]
+    chromatic_aberration_samples: i32,
+    /// Horizontal gradient weighting the samples from the center outwards, red to blue if null.
+    #[export]
+    chromatic_aberration_spectral_lut: Option<Gd<Texture2D>>,
+    /// Positive values give barrel, negative values pincushion distortion, 0.0 disables it.
+    #[export(range = (-1.0, 1.0))]
+    lens_distortion_intensity: f32,
+    /// Zoom in to hide the borders barrel distortion pulls into the screen.
+    #[export]
+    lens_distortion_crop_compensation: bool,
+    /// Counts rendered frames, animates the grain.
+    frame: u32,
+}
+
+#[godot_api]
+impl PostEffectToneMap {
+    #[func]
+    fn set_glow_levels(&mut self, glow_levels: PackedArray<f32>) {
+        if glow_levels.len() > MAX_GLOW_LEVELS {
+            godot_error!(
+                "glow_levels supports at most {} levels, got {}",
+                MAX_GLOW_LEVELS,
+                glow_levels.len()
+            );
+        }
+        let levels = glow_levels
+            .as_slice()
+            .iter()
+            .take(MAX_GLOW_LEVELS)
+            .enumerate()
+            .map(|(i, &level)| {
+                if level.is_finite() && level >= 0.0 {
+                    level
+                } else {
+                    godot_error!(
+                        "glow_levels[{}] must be a non-negative number, got {}",
+                        i,
+                        level
+                    );
+                    0.0
+                }
+            })
+            .collect::<Vec<_>>();
+        self.glow_levels = PackedArray::from(levels.as_slice());
+    }
+
+    #[func]
+    fn set_output_mode(&mut self, mode: OutputMode) {
+        self.output_mode = mode;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        self.anti_aliasing = anti_aliasing;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_glow_threshold_mode(&mut self, mode: GlowThresholdMode) {
+        self.glow_threshold_mode = mode;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_tonemap_type(&mut self, tonemap_type: ToneMapperType) {
+        self.tonemap_type = tonemap_type;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_adjustment_enabled(&mut self, enabled: bool) {
+        self.adjustment_enabled = enabled;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_grading_enabled(&mut self, enabled: bool) {
+        self.grading_enabled = enabled;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_vignette_enabled(&mut self, enabled: bool) {
+        self.vignette_enabled = enabled;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_grain_enabled(&mut self, enabled: bool) {
+        self.grain_enabled = enabled;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_grading_cdl(&mut self, path: GString) {
+        self.grading_cdl = path;
+        if self.grading_cdl.is_empty() {
+            return;
+        }
+        let src = FileAccess::get_file_as_string(&self.grading_cdl).to_string();
+        match Cdl::parse(&src) {
+            Ok(cdl) => {
+                self.grading_cdl_slope = Vector3::new(cdl.slope[0], cdl.slope[1], cdl.slope[2]);
+                self.grading_cdl_offset = Vector3::new(cdl.offset[0], cdl.offset[1], cdl.offset[2]);
+                self.grading_cdl_power = Vector3::new(cdl.power[0], cdl.power[1], cdl.power[2]);
+                self.grading_cdl_saturation = cdl.saturation;
+            }
+            Err(err) => godot_error!("Failed to load CDL {}: {}", self.grading_cdl, err),
+        }
+    }
+
+    #[func]
+    fn set_agx_look(&mut self, agx_look: AgxLook) {
+        self.agx_look = agx_look;
+        self.base_mut().notify_property_list_changed();
+    }
+
+    #[func]
+    fn set_custom_curve(&mut self, curve: Option<Gd<Curve>>) {
+        let old = std::mem::replace(&mut self.custom_curve, curve);
+        self.watch_custom_curve(old);
+    }
+
+    #[func]
+    fn set_custom_curve_r(&mut self, curve: Option<Gd<Curve>>) {
+        let old = std::mem::replace(&mut self.custom_curve_r, curve);
+        self.watch_custom_curve(old);
+    }
+
+    #[func]
+    fn set_custom_curve_g(&mut self, curve: Option<Gd<Curve>>) {
+        let old = std::mem::replace(&mut self.custom_curve_g, curve);
+        self.watch_custom_curve(old);
+    }
+
+    #[func]
+    fn set_custom_curve_b(&mut self, curve: Option<Gd<Curve>>) {
+        let old = std::mem::replace(&mut self.custom_curve_b, curve);
+        self.watch_custom_curve(old);
+    }
+
+    #[func]
+    fn mark_custom_curve_dirty(&mut self) {
+        self.custom_curve_dirty = true;
+    }
+
+    #[func]
+    fn set_color_lut(&mut self, path: GString) {
+        if self.color_lut_tex.is_valid() {
+            self.rd.free_rid(self.color_lut_tex);
+            self.color_lut_tex = Rid::Invalid;
+        }
+        self.color_lut = path;
+        if self.color_lut.is_empty() {
+            return;
+        }
+        let src = FileAccess::get_file_as_string(&self.color_lut).to_string();
+        match CubeLut::parse(&src) {
+            Ok(lut) => {
+                for warning in &lut.warnings {
+                    godot_warn!("Color LUT {}: {}", self.color_lut, warning);
+                }
+                self.color_lut_tex = create_color_lut_texture(&mut self.rd, &lut);
+                self.color_lut_domain_min = lut.domain_min;
+                self.color_lut_domain_max = lut.domain_max;
+            }
+            Err(err) => godot_error!("Failed to load color LUT {}: {}", self.color_lut, err),
+        }
+    }
+}
+
+impl PostEffectToneMap {
+    fn custom_curves(&self) -> [&Option<Gd<Curve>>; 4] {
+        [
+            &self.custom_curve,
+            &self.custom_curve_r,
+            &self.custom_curve_g,
+            &self.custom_curve_b,
+        ]
+    }
+
+    /// Moves the `changed` connection from a replaced curve to the current ones, so the
+    /// baked texture is only updated when a curve is edited.
+    fn watch_custom_curve(&mut self, old: Option<Gd<Curve>>) {
+        let callable = self.base().callable("mark_custom_curve_dirty");
+        if let Some(mut old) = old {
+            let still_used = self
+                .custom_curves()
+                .into_iter()
+                .flatten()
+                .any(|c| *c == old);
+            if !still_used && old.is_connected("changed", &callable) {
+                old.disconnect("changed", &callable);
+            }
+        }
+        for mut curve in self.custom_curves().into_iter().flatten().cloned() {
+            if !curve.is_connected("changed", &callable) {
+                curve.connect("changed", &callable);
+            }
+        }
+        self.custom_curve_dirty = true;
+    }
+
+    /// RGBA texels of the custom curve texture, missing curves act as identity.
+    fn bake_custom_curve(&self) -> PackedByteArray {
+        let sample = |curve: &Gd<Curve>, t: f32| {
+            let min = curve.get_min_domain();
+            curve.sample_baked(min + t * (curve.get_max_domain() - min))
+        };
+        let mut texels = Vec::with_capacity(CUSTOM_CURVE_SIZE);
+        for i in 0..CUSTOM_CURVE_SIZE {
+            let t = i as f32 / (CUSTOM_CURVE_SIZE - 1) as f32;
+            let v = self.custom_curve.as_ref().map_or(t, |c| sample(c, t));
+            let channel = |c: &Option<Gd<Curve>>| c.as_ref().map_or(v, |c| sample(c, v));
+            texels.push([
+                channel(&self.custom_curve_r),
+                channel(&self.custom_curve_g),
+                channel(&self.custom_curve_b),
+                1.0,
+            ]);
+        }
+        PackedByteArray::from(texels.as_slice().as_bytes())
+    }
+
+    fn update_custom_curve_texture(&mut self) {
+        if !self.custom_curve_dirty && self.custom_curve_tex.is_valid() {
+            return;
+        }
+        let data = self.bake_custom_curve();
+        if self.custom_curve_tex.is_valid() {
+            self.rd.texture_update(self.custom_curve_tex, 0, &data);
+        } else {
+            let mut format = RdTextureFormat::new_gd();
+            format.set_format(DataFormat::R32G32B32A32_SFLOAT);
+            format.set_texture_type(TextureType::TYPE_1D);
+            format.set_width(CUSTOM_CURVE_SIZE.try_into().unwrap());
+            format
+                .set_usage_bits(TextureUsageBits::SAMPLING_BIT | TextureUsageBits::CAN_UPDATE_BIT);
+            self.custom_curve_tex = self
+                .rd
+                .texture_create_ex(&format, &RdTextureView::new_gd())
+                .data(&Array::from(&[data]))
+                .done();
+        }
+        self.custom_curve_dirty = false;
+    }
+
+    fn grading_settings(&self) -> Option<GradingSettings> {
+        if !self.grading_enabled {
+            return None;
+        }
+        let rgb = |c: Color| [c.r, c.g, c.b];
+        let xyz = |v: Vector3| [v.x, v.y, v.z];
+        let mixer = [
+            xyz(self.grading_channel_mixer_red),
+            xyz(self.grading_channel_mixer_green),
+            xyz(self.grading_channel_mixer_blue),
+        ];
+        let balance = white_balance(self.grading_temperature, self.grading_tint);
+        Some(GradingSettings {
+            space: self.grading_space,
+            after_tonemap: self.grading_after_tonemap,
+            color_matrix: grading::mul(&mixer, &balance),
+            lift: rgb(self.grading_lift),
+            gamma: rgb(self.grading_gamma),
+            gain: rgb(self.grading_gain),
+            cdl: Cdl {
+                slope: xyz(self.grading_cdl_slope),
+                offset: xyz(self.grading_cdl_offset),
+                power: xyz(self.grading_cdl_power),
+                saturation: self.grading_cdl_saturation,
+            },
+        })
+    }
+
+    fn vignette_settings(&self) -> Option<VignetteSettings> {
+        if !self.vignette_enabled {
+            return None;
+        }
+        let mut mask_tex = Rid::Invalid;
+        if let Some(tex) = self.vignette_mask.as_ref() {
+            let rid = tex.get_rid();
+            if rid.is_valid() {
+                mask_tex = RenderingServer::singleton().texture_get_rd_texture(rid);
+            }
+        }
+        Some(VignetteSettings {
+            intensity: self.vignette_intensity,
+            smoothness: self.vignette_smoothness,
+            roundness: self.vignette_roundness,
+            center: self.vignette_center,
+            color: self.vignette_color,
+            mask_tex,
+            after_tonemap: self.vignette_after_tonemap,
+        })
+    }
+
+    fn grain_settings(&self) -> Option<GrainSettings> {
+        if !self.grain_enabled {
+            return None;
+        }
+        let mut tex = Rid::Invalid;
+        if let Some(grain_texture) = self.grain_texture.as_ref() {
+            let rid = grain_texture.get_rid();
+            if rid.is_valid() {
+                tex = RenderingServer::singleton().texture_get_rd_texture(rid);
+            }
+        }
+        Some(GrainSettings {
+            intensity: self.grain_intensity,
+            size: self.grain_size,
+            response: self.grain_response,
+            tex,
+        })
+    }
+
+    fn hdr_output_settings(&self) -> Option<HdrOutputSettings> {
+        (self.output_mode != OutputMode::Sdr).then_some(HdrOutputSettings {
+            mode: self.output_mode,
+            paper_white_nits: self.output_paper_white_nits,
+            max_nits: self.output_max_nits,
+        })
+    }
+
+    fn chromatic_aberration_settings(&self) -> Option<ChromaticAberrationSettings> {
+        if self.chromatic_aberration_intensity <= 0.0 {
+            return None;
+        }
+        let mut spectral_lut_tex = Rid::Invalid;
+        if let Some(tex) = self.chromatic_aberration_spectral_lut.as_ref() {
+            let rid = tex.get_rid();
+            if rid.is_valid() {
+                spectral_lut_tex = RenderingServer::singleton().texture_get_rd_texture(rid);
+            }
+        }
+        Some(ChromaticAberrationSettings {
+            intensity: self.chromatic_aberration_intensity,
+            samples: self.chromatic_aberration_samples,
+            spectral_lut_tex,
+        })
+    }
+
+    fn tonemap_params(&self) -> [[f32; 4]; 3] {
+        let mut params = [[0.0; 4]; 3];
+        // Relative to paper white, 1.0 in SDR.
+        let peak = self.hdr_output_settings().map_or(1.0, |hdr| hdr.peak());
+        match self.tonemap_type {
+            ToneMapperType::Agx => {
+                let look = self.agx_look.preset().unwrap_or(AgxLookParams {
+                    offset: [
+                        self.agx_look_offset.x,
+                        self.agx_look_offset.y,
+                        self.agx_look_offset.z,
+                    ],
+                    slope: [
+                        self.agx_look_slope.x,
+                        self.agx_look_slope.y,
+                        self.agx_look_slope.z,
+                    ],
+                    power: [
+                        self.agx_look_power.x,
+                        self.agx_look_power.y,
+                        self.agx_look_power.z,
+                    ],
+                    saturation: self.agx_look_saturation,
+                });
+                let [sx, sy, sz] = look.slope;
+                let [ox, oy, oz] = look.offset;
+                let [px, py, pz] = look.power;
+                params[0] = [sx, sy, sz, look.saturation];
+                params[1] = [ox, oy, oz, self.agx_contrast];
+                params[2] = [px, py, pz, 0.0];
+            }
+            ToneMapperType::Gt => {
+                params[0] = [
+                    self.gt_max_brightness * peak,
+                    self.gt_contrast,
+                    self.gt_linear_start,
+                    self.gt_linear_length,
+                ];
+                params[1][0] = self.gt_black;
+                params[1][1] = self.gt_pedestal;
+            }
+            ToneMapperType::Lottes => {
+                // Solved for an output of 1.0 and scaled to the peak, so mid grey stays put.
+                let (b, c) = lottes_coefficients(
+                    self.lottes_contrast,
+                    self.lottes_shoulder,
+                    self.lottes_hdr_max,
+                    self.lottes_mid_in,
+                    self.lottes_mid_out / peak,
+                );
+                params[0] = [
+                    self.lottes_contrast,
+                    self.lottes_shoulder,
+                    b / peak,
+                    c / peak,
+                ];
+            }
+            ToneMapperType::Hable => {
+                let curve = |x| {
+                    hable_curve(
+                        x,
+                        self.hable_shoulder_strength,
+                        self.hable_linear_strength,
+                        self.hable_linear_angle,
+                        self.hable_toe_strength,
+                        self.hable_toe_numerator,
+                        self.hable_toe_denominator,
+                    )
+                };
+                params[0] = [
+                    self.hable_shoulder_strength,
+                    self.hable_linear_strength,
+                    self.hable_linear_angle,
+                    self.hable_toe_strength,
+                ];
+                params[1] = [
+                    self.hable_toe_numerator,
+                    self.hable_toe_denominator,
+                    1.0 / curve(self.hable_white_point),
+                    0.0,
+                ];
+            }
+            ToneMapperType::Custom => {
+                params[0][0] = self.custom_curve_min_ev;
+                params[0][1] =
+                    1.0 / (self.custom_curve_max_ev - self.custom_curve_min_ev).max(1e-3);
+            }
+            _ => {}
+        }
+        params
+    }
+
+    /// Adapts towards the latest histogram read back from the GPU and returns the exposure.
+    fn update_auto_exposure(&mut self) -> f32 {
+        let settings = AutoExposureSettings {
+            min_ev: self.auto_exposure_min_ev,
+            max_ev: self.auto_exposure_max_ev,
+            low_percent: self.auto_exposure_low_percent,
+            high_percent: self.auto_exposure_high_percent,
+            compensation: self.auto_exposure_compensation,
+            speed_up: self.auto_exposure_speed_up,
+            speed_down: self.auto_exposure_speed_down,
+        };
+        let now = Time::singleton().get_ticks_usec();
+        let delta = if self.auto_exposure_last_usec == 0 {
+            0.0
+        } else {
+            now.saturating_sub(self.auto_exposure_last_usec) as f32 * 1e-6
+        };
+        self.auto_exposure_last_usec = now;
+
+        if let Some(target_ev) = self
+            .luminance_histogram
+            .take_bins()
+            .and_then(|bins| histogram_average_ev(&bins, &settings))
+        {
+            self.auto_exposure_target_ev = Some(target_ev);
+        }
+        if let Some(target_ev) = self.auto_exposure_target_ev {
+            self.auto_exposure_ev = Some(match self.auto_exposure_ev {
+                // Snap to the first measurement instead of fading in from black.
+                None => target_ev,
+                Some(ev) => adapt_ev(ev, target_ev, delta, &settings),
+            });
+        }
+        self.auto_exposure_ev.map_or(self.exposure, |ev| {
+            exposure_from_ev(ev, settings.compensation)
+        })
+    }
+}
+
+impl PostEffectToneMap {
+    /// Exposure of the camera attributes with the given RID, or the manual exposure until
+    /// they have been found.
+    fn camera_exposure(&mut self, rid: Rid) -> f32 {
+        if !rid.is_valid() {
+            return self.exposure;
+        }
+        let instance_id = {
+            let mut lookup = self.camera_attributes.lock().unwrap();
+            let instance_id = lookup.found.get(&rid).copied();
+            let now = Time::singleton().get_ticks_usec();
+            let retry = lookup.missed.get(&rid).is_none_or(|&missed_usec| {
+                now.saturating_sub(missed_usec) >= CAMERA_ATTRIBUTES_RETRY_USEC
+            });
+            if instance_id.is_none() && retry && lookup.pending != Some(rid) {
+                lookup.pending = Some(rid);
+                request_camera_attributes_lookup(self.camera_attributes.clone(), rid);
+            }
+            instance_id
+        };
+        let Some(attributes) =
+            instance_id.and_then(|id| Gd::<CameraAttributes>::try_from_instance_id(id).ok())
+        else {
+            return self.exposure;
+        };
+        let multiplier = attributes.get_exposure_multiplier();
+        // With physical light units Godot already scales the scene lighting by the camera's
+        // exposure normalization, applying EV100 again would expose twice.
+        match attributes.try_cast::<CameraAttributesPhysical>() {
+            Ok(physical) if !self.physical_light_units => {
+                multiplier
+                    * relative_exposure(ev100(
+                        physical.get_aperture(),
+                        physical.get_shutter_speed(),
+                        physical.get_exposure_sensitivity(),
+                    ))
+            }
+            _ => multiplier,
+        }
+    }
+}
+
+/// Time after which a failed camera attributes lookup is retried. The camera may not be current
+/// or its viewport not in the tree yet.
+const CAMERA_ATTRIBUTES_RETRY_USEC: u64 = 500_000;
+
+/// Camera attributes resources by RID, found on the main thread.
+#[derive(Default)]
+struct CameraAttributesLookup {
+    pending: Option<Rid>,
+    found: HashMap<Rid, InstanceId>,
+    /// Time of the last failed lookup, so the scene tree isn't searched again every frame.
+    missed: HashMap<Rid, u64>,
+}
+
+/// The scene tree can't be walked from the render thread, defer the search to the main thread.
+fn request_camera_attributes_lookup(lookup: Arc<Mutex<CameraAttributesLookup>>, rid: Rid) {
+    let callable = Callable::from_sync_fn("camera_attributes_lookup", move |_| {
+        let found = find_camera_attributes(rid).map(|attributes| attributes.instance_id());
+        let mut lookup = lookup.lock().unwrap();
+        lookup.pending = None;
+        match found {
+            Some(instance_id) => {
+                lookup.missed.remove(&rid);
+                lookup.found.insert(rid, instance_id);
+            }
+            None => {
+                let now = Time::singleton().get_ticks_usec();
+                lookup.missed.insert(rid, now);
+            }
+        }
+        Ok(Variant::nil())
+    });
+    callable.call_deferred(&[]);
+}
+
+/// `RenderData` only exposes the RID of the camera attributes, so look the resource up on the
+/// cameras and worlds of the viewports in the scene tree, sub-viewports and embedded windows
+/// included. Main thread only.
+fn find_camera_attributes(rid: Rid) -> Option<Gd<CameraAttributes>> {
+    let tree = Engine::singleton()
+        .get_main_loop()?
+        .try_cast::<SceneTree>()
+        .ok()?;
+    let root = tree.get_root()?;
+    let mut viewports: Vec<Gd<Viewport>> = vec![root.clone().upcast()];
+    viewports.extend(
+        root.find_children_ex("*")
+            .type_("Viewport")
+            .owned(false)
+            .done()
+            .iter_shared()
+            .map(|node| node.cast::<Viewport>()),
+    );
+    viewports.iter().find_map(|viewport| {
+        let camera_attributes = viewport
+            .get_camera_3d()
+            .and_then(|camera| camera.get_attributes());
+        let world_attributes = viewport
+            .get_world_3d()
+            .and_then(|world| world.get_camera_attributes());
+        [camera_attributes, world_attributes]
+            .into_iter()
+            .flatten()
+            .find(|attributes| attributes.get_rid() == rid)
+    })
+}
+
+impl Drop for PostEffectToneMap {
+    fn drop(&mut self) {
+        if self.color_lut_tex.is_valid() {
+            self.rd.free_rid(self.color_lut_tex);
+        }
+        if self.custom_curve_tex.is_valid() {
+            self.rd.free_rid(self.custom_curve_tex);
+        }
+    }
 }
 
 #[godot_api]
@@ -82,7 +1025,7 @@ impl ICompositorEffect for PostEffectToneMap {
         }
 
         let rd = RenderingServer::singleton().get_rendering_device().unwrap();
-        let glow_levels = [1.0, 1.0, 1.0];
+        let glow_levels = [1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0];
         let glow_intensity: f32 = 0.8;
         let glow_strength: f32 = 1.0;
         let glow_mix: f32 = 0.05;
@@ -93,35 +1036,249 @@ impl ICompositorEffect for PostEffectToneMap {
         let glow_hdr_luminance_cap: f32 = 12.0;
         let glow_map_strength: f32 = 0.8;
         let glow_map: Option<Gd<Texture2D>> = None;
+        let exposure_mode = ExposureMode::Manual;
         let exposure: f32 = 1.0;
+        let auto_exposure_min_ev: f32 = -8.0;
+        let auto_exposure_max_ev: f32 = 8.0;
+        let auto_exposure_low_percent: f32 = 10.0;
+        let auto_exposure_high_percent: f32 = 5.0;
+        let auto_exposure_compensation: f32 = 0.0;
+        let auto_exposure_speed_up: f32 = 3.0;
+        let auto_exposure_speed_down: f32 = 1.0;
         let white: f32 = 2.0;
         let tonemap_type = ToneMapperType::Reinhard;
+        let tonemap_mode = ToneMapMode::PerChannel;
+        let tonemap_mode_blend: f32 = 1.0;
+        let agx_contrast: f32 = 1.0;
+        let agx_look = AgxLook::None;
+        let agx_look_offset = Vector3::ZERO;
+        let agx_look_slope = Vector3::ONE;
+        let agx_look_power = Vector3::ONE;
+        let agx_look_saturation: f32 = 1.0;
+        let gt_max_brightness: f32 = 1.0;
+        let gt_contrast: f32 = 1.0;
+        let gt_linear_start: f32 = 0.22;
+        let gt_linear_length: f32 = 0.4;
+        let gt_black: f32 = 1.33;
+        let gt_pedestal: f32 = 0.0;
+        let lottes_contrast: f32 = 1.6;
+        let lottes_shoulder: f32 = 0.977;
+        let lottes_hdr_max: f32 = 8.0;
+        let lottes_mid_in: f32 = 0.18;
+        let lottes_mid_out: f32 = 0.267;
+        let hable_shoulder_strength: f32 = 0.15;
+        let hable_linear_strength: f32 = 0.5;
+        let hable_linear_angle: f32 = 0.1;
+        let hable_toe_strength: f32 = 0.2;
+        let hable_toe_numerator: f32 = 0.02;
+        let hable_toe_denominator: f32 = 0.3;
+        let hable_white_point: f32 = 11.2;
+        let custom_curve_min_ev: f32 = -10.0;
+        let custom_curve_max_ev: f32 = 4.0;
         Self {
             base,
             rd,
             downsample: BlurDownsample::init(),
             upsample: BlurUpsample::init(),
+            streak: Streak::init(),
+            lens_flare: LensFlare::init(),
+            cas: Cas::init(),
+            smaa: Smaa::init(),
             tonemapper: ToneMapper::init(),
             copy: TexCopy::init(),
+            luminance_histogram: LuminanceHistogram::init(),
             global_rids_singleton: Engine::singleton()
                 .get_singleton(&GlobalRidsSingleton::class_name().to_string_name())
                 .unwrap()
                 .cast::<GlobalRidsSingleton>(),
             glow_levels: PackedArray::from(&glow_levels),
-            use_fxaa: false,
+            glow_level_tints: PackedArray::new(),
+            anti_aliasing: AntiAliasing::None,
+            fxaa_quality: FxaaQuality::Preset39,
+            fxaa_subpixel_quality: 0.75,
+            fxaa_edge_threshold: 0.125,
+            fxaa_edge_threshold_min: 0.0312,
+            use_debanding: false,
+            output_mode: OutputMode::Sdr,
+            output_paper_white_nits: 200.0,
+            output_max_nits: 1000.0,
+            sharpen_intensity: 0.0,
+            sharpen_with_fxaa: false,
             glow_intensity,
             glow_strength,
             glow_mix,
             glow_bloom,
             glow_blend_mode,
+            glow_threshold_mode: GlowThresholdMode::Smoothstep,
             glow_hdr_bleed_threshold,
             glow_hdr_bleed_scale,
+            glow_threshold_ev: 0.0,
+            glow_threshold_knee_ev: 1.0,
             glow_hdr_luminance_cap,
+            glow_firefly_suppression: 0.0,
             glow_map_strength,
             glow_map,
+            lens_dirt_texture: None,
+            lens_dirt_intensity: 1.0,
+            lens_dirt_glow_level: 4,
+            streak_intensity: 0.0,
+            streak_direction: Vector2::new(1.0, 0.0),
+            streak_length: 0.95,
+            streak_tint: Color::from_rgb(0.5, 0.6, 1.0),
+            lens_flare_intensity: 0.0,
+            lens_flare_ghost_count: 4,
+            lens_flare_ghost_dispersal: 0.35,
+            lens_flare_ghost_gradient: None,
+            lens_flare_halo_width: 0.45,
+            lens_flare_halo_intensity: 0.5,
+            lens_flare_chromatic_distortion: 2.0,
+            lens_flare_starburst_intensity: 0.5,
+            exposure_mode,
             exposure,
+            auto_exposure_min_ev,
+            auto_exposure_max_ev,
+            auto_exposure_low_percent,
+            auto_exposure_high_percent,
+            auto_exposure_compensation,
+            auto_exposure_speed_up,
+            auto_exposure_speed_down,
+            auto_exposure_target_ev: None,
+            auto_exposure_ev: None,
+            auto_exposure_last_usec: 0,
+            camera_attributes: Arc::new(Mutex::new(CameraAttributesLookup::default())),
+            physical_light_units: ProjectSettings::singleton()
+                .get_setting("rendering/lights_and_shadows/use_physical_light_units")
+                .booleanize(),
             white,
             tonemap_type,
+            tonemap_mode,
+            tonemap_mode_blend,
+            agx_contrast,
+            agx_look,
+            agx_look_offset,
+            agx_look_slope,
+            agx_look_power,
+            agx_look_saturation,
+            gt_max_brightness,
+            gt_contrast,
+            gt_linear_start,
+            gt_linear_length,
+            gt_black,
+            gt_pedestal,
+            lottes_contrast,
+            lottes_shoulder,
+            lottes_hdr_max,
+            lottes_mid_in,
+            lottes_mid_out,
+            hable_shoulder_strength,
+            hable_linear_strength,
+            hable_linear_angle,
+            hable_toe_strength,
+            hable_toe_numerator,
+            hable_toe_denominator,
+            hable_white_point,
+            custom_curve: None,
+            custom_curve_r: None,
+            custom_curve_g: None,
+            custom_curve_b: None,
+            custom_curve_min_ev,
+            custom_curve_max_ev,
+            custom_curve_tex: Rid::Invalid,
+            custom_curve_dirty: true,
+            color_lut: GString::new(),
+            color_lut_tex: Rid::Invalid,
+            color_lut_domain_min: [0.0; 3],
+            color_lut_domain_max: [1.0; 3],
+            adjustment_enabled: false,
+            adjustment_brightness: 1.0,
+            adjustment_contrast: 1.0,
+            adjustment_saturation: 1.0,
+            adjustment_color_correction: None,
+            grading_enabled: false,
+            grading_space: GradingSpace::AcesCc,
+            grading_after_tonemap: false,
+            grading_temperature: NEUTRAL_TEMPERATURE,
+            grading_tint: 0.0,
+            grading_lift: Color::BLACK,
+            grading_gamma: Color::WHITE,
+            grading_gain: Color::WHITE,
+            grading_channel_mixer_red: Vector3::new(1.0, 0.0, 0.0),
+            grading_channel_mixer_green: Vector3::new(0.0, 1.0, 0.0),
+            grading_channel_mixer_blue: Vector3::new(0.0, 0.0, 1.0),
+            grading_cdl: GString::new(),
+            grading_cdl_slope: Vector3::ONE,
+            grading_cdl_offset: Vector3::ZERO,
+            grading_cdl_power: Vector3::ONE,
+            grading_cdl_saturation: 1.0,
+            vignette_enabled: false,
+            vignette_intensity: 0.4,
+            vignette_smoothness: 0.2,
+            vignette_roundness: 1.0,
+            vignette_center: Vector2::ZERO,
+            vignette_color: Color::BLACK,
+            vignette_mask: None,
+            vignette_after_tonemap: false,
+            grain_enabled: false,
+            grain_intensity: 0.3,
+            grain_size: 1.5,
+            grain_response: 0.8,
+            grain_texture: None,
+            chromatic_aberration_intensity: 0.0,
+            chromatic_aberration_samples: 8,
+            chromatic_aberration_spectral_lut: None,
+            lens_distortion_intensity: 0.0,
+            lens_distortion_crop_compensation: true,
+            frame: 0,
+        }
+    }
+
+    fn set_property(&mut self, property: StringName, value: Variant) -> bool {
+        // `use_fxaa` was replaced by `anti_aliasing`, keep FXAA on in resources saved before.
+        if property == StringName::from(c"use_fxaa") {
+            if value.booleanize() {
+                self.anti_aliasing = AntiAliasing::Fxaa;
+            }
+            return true;
+        }
+        false
+    }
+
+    fn validate_property(&self, property: &mut PropertyInfo) {
+        // Only show the parameters of the selected tonemapper.
+        let name = property.property_name.to_string();
+        let visible = if name.starts_with("agx_look_") {
+            self.tonemap_type == ToneMapperType::Agx && self.agx_look == AgxLook::Custom
+        } else if name.starts_with("agx_") {
+            self.tonemap_type == ToneMapperType::Agx
+        } else if name.starts_with("gt_") {
+            self.tonemap_type == ToneMapperType::Gt
+        } else if name.starts_with("lottes_") {
+            self.tonemap_type == ToneMapperType::Lottes
+        } else if name.starts_with("hable_") {
+            self.tonemap_type == ToneMapperType::Hable
+        } else if name.starts_with("custom_curve") {
+            self.tonemap_type == ToneMapperType::Custom
+        } else if name.starts_with("output_") && name != "output_mode" {
+            self.output_mode != OutputMode::Sdr
+        } else if name.starts_with("fxaa_") {
+            self.anti_aliasing == AntiAliasing::Fxaa
+        } else if name.starts_with("glow_hdr_bleed_") {
+            self.glow_threshold_mode == GlowThresholdMode::Smoothstep
+        } else if name.starts_with("glow_threshold_") && name != "glow_threshold_mode" {
+            self.glow_threshold_mode == GlowThresholdMode::SoftKneeEv
+        } else if name.starts_with("adjustment_") && name != "adjustment_enabled" {
+            self.adjustment_enabled
+        } else if name.starts_with("grading_") && name != "grading_enabled" {
+            self.grading_enabled
+        } else if name.starts_with("vignette_") && name != "vignette_enabled" {
+            self.vignette_enabled
+        } else if name.starts_with("grain_") && name != "grain_enabled" {
+            self.grain_enabled
+        } else {
+            true
+        };
+        if !visible {
+            property.usage = PropertyUsageFlags::NO_EDITOR;
         }
     }
 
@@ -129,6 +1286,7 @@ impl ICompositorEffect for PostEffectToneMap {
         if effect_callback_type != EffectCallbackType::POST_TRANSPARENT.ord() {
             return;
         }
+        self.frame = self.frame.wrapping_add(1);
         let data = render_data.unwrap();
         let mut rb = data
             .get_render_scene_buffers()
@@ -142,6 +1300,25 @@ impl ICompositorEffect for PostEffectToneMap {
         let scope = &*RB_SCOPE_BUFFERS;
         let blur0 = &*RB_TEX_BLUR_0;
         let blur1 = &*RB_TEX_BLUR_1;
+        let blur1_size = Vector2i {
+            x: (buffer_size.x >> 1).max(1),
+            y: (buffer_size.y >> 1).max(1),
+        };
+        let blur0_mipmaps = get_image_required_mipmaps(
+            buffer_size.x.try_into().unwrap(),
+            buffer_size.y.try_into().unwrap(),
+            1,
+        );
+        let blur1_mipmaps = get_image_required_mipmaps(
+            blur1_size.x.try_into().unwrap(),
+            blur1_size.y.try_into().unwrap(),
+            1,
+        );
+        // Level i needs mip i + 1 of blur_1 and mip i + 2 of blur_0, small viewports get fewer.
+        let glow_level_count: usize = (blur1_mipmaps.saturating_sub(1))
+            .min(blur0_mipmaps.saturating_sub(2))
+            .try_into()
+            .unwrap();
         let _tex_blur0 = rb.create_texture(
             scope,
             blur0,
@@ -152,11 +1329,7 @@ impl ICompositorEffect for PostEffectToneMap {
             TextureSamples::SAMPLES_1,
             buffer_size,
             1,
-            get_image_required_mipmaps(
-                buffer_size.x.try_into().unwrap(),
-                buffer_size.y.try_into().unwrap(),
-                1,
-            ),
+            blur0_mipmaps,
             true,
             false,
         );
@@ -168,20 +1341,165 @@ impl ICompositorEffect for PostEffectToneMap {
                 .try_into()
                 .unwrap(),
             TextureSamples::SAMPLES_1,
-            Vector2i {
-                x: buffer_size.x >> 1,
-                y: buffer_size.y >> 1,
-            },
+            blur1_size,
             1,
-            get_image_required_mipmaps(
-                (buffer_size.x >> 1).try_into().unwrap(),
-                (buffer_size.y >> 1).try_into().unwrap(),
-                1,
-            ),
+            blur1_mipmaps,
             true,
             false,
         );
+        let streak_direction = self.streak_direction.try_normalized();
+        let use_streak =
+            glow_level_count > 0 && self.streak_intensity > 0.0 && streak_direction.is_some();
+        let mut streak_size = Vector2i::ONE;
+        if use_streak {
+            // The first downsample the streaks run on is mip 1 of blur_1.
+            streak_size = rb.get_texture_slice_size(scope, blur1, 1);
+            for name in [&*RB_TEX_STREAK_0, &*RB_TEX_STREAK_1] {
+                rb.create_texture(
+                    scope,
+                    name,
+                    color_data_fmt,
+                    (TextureUsageBits::COLOR_ATTACHMENT_BIT.ord()
+                        | TextureUsageBits::SAMPLING_BIT.ord())
+                    .try_into()
+                    .unwrap(),
+                    TextureSamples::SAMPLES_1,
+                    streak_size,
+                    1,
+                    1,
+                    true,
+                    false,
+                );
+            }
+        }
+        let use_lens_flare = glow_level_count > 0 && self.lens_flare_intensity > 0.0;
+        if use_lens_flare {
+            rb.create_texture(
+                scope,
+                &*RB_TEX_LENS_FLARE,
+                color_data_fmt,
+                (TextureUsageBits::COLOR_ATTACHMENT_BIT.ord()
+                    | TextureUsageBits::SAMPLING_BIT.ord())
+                .try_into()
+                .unwrap(),
+                TextureSamples::SAMPLES_1,
+                rb.get_texture_slice_size(scope, blur1, 1),
+                1,
+                1,
+                true,
+                false,
+            );
+        }
+        let use_fxaa = self.anti_aliasing == AntiAliasing::Fxaa;
+        let use_fxaa_sharpen = use_fxaa && self.sharpen_with_fxaa && self.sharpen_intensity > 0.0;
+        let use_cas = self.sharpen_intensity > 0.0 && !use_fxaa_sharpen;
+        if use_cas {
+            // The tonemapper renders here, CAS writes it back to the color layer.
+            rb.create_texture(
+                scope,
+                &*RB_TEX_SHARPEN,
+                color_data_fmt,
+                (TextureUsageBits::COLOR_ATTACHMENT_BIT.ord()
+                    | TextureUsageBits::SAMPLING_BIT.ord())
+                .try_into()
+                .unwrap(),
+                TextureSamples::SAMPLES_1,
+                buffer_size,
+                1,
+                1,
+                true,
+                false,
+            );
+        }
+        let smaa_preset = self.anti_aliasing.smaa_preset();
+        if smaa_preset.is_some() {
+            // The tonemapper renders to `smaa_source`, the blending pass writes to the color
+            // layer or the CAS source.
+            for (name, format) in [
+                (&*RB_TEX_SMAA_SOURCE, color_data_fmt),
+                (&*RB_TEX_SMAA_EDGES, DataFormat::R8G8_UNORM),
+                (&*RB_TEX_SMAA_BLEND, DataFormat::R8G8B8A8_UNORM),
+            ] {
+                rb.create_texture(
+                    scope,
+                    name,
+                    format,
+                    (TextureUsageBits::COLOR_ATTACHMENT_BIT.ord()
+                        | TextureUsageBits::SAMPLING_BIT.ord())
+                    .try_into()
+                    .unwrap(),
+                    TextureSamples::SAMPLES_1,
+                    buffer_size,
+                    1,
+                    1,
+                    true,
+                    false,
+                );
+            }
+        }
+        let mut lens_flare_ghost_gradient = Rid::Invalid;
+        if let Some(tex) = self.lens_flare_ghost_gradient.as_ref() {
+            let rid = tex.get_rid();
+            if rid.is_valid() {
+                lens_flare_ghost_gradient =
+                    RenderingServer::singleton().texture_get_rd_texture(rid);
+            }
+        }
+        // Rotate the starburst with the camera (John Chapman's pseudo lens flare).
+        let lens_flare_starburst_rotation = data
+            .get_render_scene_data()
+            .map(|scene_data| {
+                let basis = scene_data.get_cam_transform().basis;
+                (basis.col_a().z + basis.col_c().y) * std::f32::consts::PI
+            })
+            .unwrap_or(0.0);
+        let exposure = match self.exposure_mode {
+            ExposureMode::Manual => self.exposure,
+            ExposureMode::Auto => {
+                self.luminance_histogram.exec(
+                    color_tex,
+                    buffer_size,
+                    self.auto_exposure_min_ev,
+                    self.auto_exposure_max_ev,
+                );
+                self.update_auto_exposure()
+            }
+            ExposureMode::Camera => self.camera_exposure(data.get_camera_attributes()),
+        };
+
+        let custom_curve_tex = if self.tonemap_type == ToneMapperType::Custom {
+            self.update_custom_curve_texture();
+            self.custom_curve_tex
+        } else {
+            Rid::Invalid
+        };
+
+        let mut color_correction = Rid::Invalid;
+        let mut color_correction_is_1d = false;
+        if self.adjustment_enabled
+            && let Some(tex) = self.adjustment_color_correction.as_ref()
+        {
+            let rid = tex.get_rid();
+            if rid.is_valid() {
+                color_correction = RenderingServer::singleton().texture_get_rd_texture(rid);
+                color_correction_is_1d = tex.clone().try_cast::<Texture2D>().is_ok();
+            }
+        }
+
+        let glow_threshold = match self.glow_threshold_mode {
+            GlowThresholdMode::Smoothstep => GlowThreshold::Smoothstep,
+            GlowThresholdMode::SoftKneeEv => {
+                GlowThreshold::soft_knee_ev(self.glow_threshold_ev, self.glow_threshold_knee_ev)
+            }
+        };
         let glow_levels = self.glow_levels.as_slice();
+        let glow_level_tints = self.glow_level_tints.as_slice();
+        let glow_level_tint = |i: i32| -> Color {
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| glow_level_tints.get(i).copied())
+                .unwrap_or(Color::WHITE)
+        };
         let mut glow_intensity: f32 = self.glow_intensity;
         let mut glow_map = Rid::Invalid;
         if self.glow_map.is_some() {
@@ -194,7 +1512,22 @@ impl ICompositorEffect for PostEffectToneMap {
         if self.glow_blend_mode == GlowMode::Mix {
             glow_intensity = self.glow_mix;
         }
+        let mut lens_dirt = Rid::Invalid;
+        if let Some(tex) = self.lens_dirt_texture.as_ref() {
+            let rid = tex.get_rid();
+            if rid.is_valid() {
+                lens_dirt = RenderingServer::singleton().texture_get_rd_texture(rid);
+            }
+        }
+        // Clamped to the levels the viewport has mips for, -1 disables the dirt.
+        let lens_dirt_glow_level: i32 = if lens_dirt.is_valid() && glow_level_count > 0 {
+            self.lens_dirt_glow_level
+                .clamp(0, i32::try_from(glow_level_count).unwrap() - 1)
+        } else {
+            -1
+        };
 
+        let glow_levels = &glow_levels[..glow_levels.len().min(glow_level_count)];
         let mut max_glow_index: i32 = -1;
         let mut min_glow_level: i32 = glow_levels.len().try_into().unwrap();
         for i in 0i32..glow_levels.len().try_into().unwrap() {
@@ -206,111 +1539,291 @@ impl ICompositorEffect for PostEffectToneMap {
 
         for layer in 0..rb.get_view_count() {
             let color_tex = rb.get_color_layer(layer);
-            let mut source = color_tex;
-            let mut dest = rb.get_texture_slice(scope, blur1, 0, 1, 1, 1);
-            let mut source_size = buffer_size;
-            let luminance_multiplier = 2.0f32;
-            // Downsample.
-            self.downsample.exec(
-                source,
-                dest,
-                luminance_multiplier,
-                source_size,
-                self.glow_strength,
-                true,
-                self.glow_hdr_luminance_cap,
-                self.exposure,
-                self.glow_bloom,
-                self.glow_hdr_bleed_threshold,
-                self.glow_hdr_bleed_scale,
-            );
-            let mut vp_size;
-            for i in 1..max_glow_index + 1 {
-                source = dest;
-                vp_size = rb.get_texture_slice_size(scope, blur1, i.try_into().unwrap());
-                dest = rb.get_texture_slice(scope, blur1, 0, (i + 1).try_into().unwrap(), 1, 1);
+            // Too small for even the first level, render without glow.
+            if glow_level_count > 0 {
+                let mut source = color_tex;
+                let mut dest = rb.get_texture_slice(scope, blur1, 0, 1, 1, 1);
+                let mut source_size = buffer_size;
+                let luminance_multiplier = 2.0f32;
+                // Downsample.
                 self.downsample.exec(
                     source,
                     dest,
                     luminance_multiplier,
-                    vp_size,
+                    source_size,
                     self.glow_strength,
-                    false,
+                    true,
                     self.glow_hdr_luminance_cap,
-                    self.exposure,
+                    exposure,
                     self.glow_bloom,
                     self.glow_hdr_bleed_threshold,
                     self.glow_hdr_bleed_scale,
+                    self.glow_firefly_suppression,
+                    glow_threshold,
                 );
+                let mut vp_size;
+                // The lens dirt level may be wider than any enabled glow level.
+                for i in 1..std::cmp::max(max_glow_index, lens_dirt_glow_level) + 1 {
+                    source = dest;
+                    vp_size = rb.get_texture_slice_size(scope, blur1, i.try_into().unwrap());
+                    dest = rb.get_texture_slice(scope, blur1, 0, (i + 1).try_into().unwrap(), 1, 1);
+                    self.downsample.exec(
+                        source,
+                        dest,
+                        luminance_multiplier,
+                        vp_size,
+                        self.glow_strength,
+                        false,
+                        self.glow_hdr_luminance_cap,
+                        exposure,
+                        self.glow_bloom,
+                        self.glow_hdr_bleed_threshold,
+                        self.glow_hdr_bleed_scale,
+                        0.0,
+                        glow_threshold,
+                    );
+                }
+                // Upsample.
+                if max_glow_index <= 0 {
+                    source = self.global_rids_singleton.bind().default_texture_black;
+                    vp_size = rb.get_texture_slice_size(scope, blur0, 2);
+                    dest = rb.get_texture_slice(scope, blur0, 0, 2, 1, 1);
+                    let blend_tex = rb.get_texture_slice(scope, blur1, 0, 1, 1, 1);
+                    source_size = vp_size;
+                    self.upsample.exec(
+                        source,
+                        dest,
+                        blend_tex,
+                        source_size,
+                        vp_size,
+                        glow_levels.first().copied().unwrap_or(0.0),
+                        0.0,
+                        glow_level_tint(0),
+                        Color::WHITE,
+                    );
+                }
+                for i in (0..max_glow_index).rev() {
+                    source = dest;
+                    source_size =
+                        rb.get_texture_slice_size(scope, blur0, (i + 3).try_into().unwrap());
+                    vp_size = rb.get_texture_slice_size(scope, blur0, (i + 2).try_into().unwrap());
+                    dest = rb.get_texture_slice(scope, blur0, 0, (i + 2).try_into().unwrap(), 1, 1);
+                    let blend_tex =
+                        rb.get_texture_slice(scope, blur1, 0, (i + 1).try_into().unwrap(), 1, 1);
+                    self.upsample.exec(
+                        source,
+                        dest,
+                        blend_tex,
+                        source_size,
+                        vp_size,
+                        glow_levels[TryInto::<usize>::try_into(i).unwrap()],
+                        if i == max_glow_index - 1 {
+                            glow_levels[TryInto::<usize>::try_into(i + 1).unwrap()]
+                        } else {
+                            1.0
+                        },
+                        glow_level_tint(i),
+                        if i == max_glow_index - 1 {
+                            glow_level_tint(i + 1)
+                        } else {
+                            Color::WHITE
+                        },
+                    );
+                }
             }
-            // Upsample.
-            if max_glow_index <= 0 {
-                source = self.global_rids_singleton.bind().default_texture_black;
-                vp_size = rb.get_texture_slice_size(scope, blur0, 2);
-                dest = rb.get_texture_slice(scope, blur0, 0, 2, 1, 1);
-                let blend_tex = rb.get_texture_slice(scope, blur1, 0, 1, 1, 1);
-                source_size = vp_size;
-                self.upsample.exec(
-                    source,
-                    dest,
-                    blend_tex,
-                    source_size,
-                    vp_size,
-                    glow_levels[0],
-                    0.0,
-                );
+            let mut streak = Rid::Invalid;
+            if use_streak {
+                let streak_tex = [
+                    rb.get_texture_slice(scope, &*RB_TEX_STREAK_0, 0, 0, 1, 1),
+                    rb.get_texture_slice(scope, &*RB_TEX_STREAK_1, 0, 0, 1, 1),
+                ];
+                streak = rb.get_texture_slice(scope, blur1, 0, 1, 1, 1);
+                for pass in 0..STREAK_PASSES {
+                    let dest = streak_tex[usize::try_from(pass % 2).unwrap()];
+                    self.streak.exec(
+                        streak,
+                        dest,
+                        streak_size,
+                        pass,
+                        streak_direction.unwrap(),
+                        self.streak_length,
+                        self.streak_tint * self.streak_intensity,
+                    );
+                    streak = dest;
+                }
             }
-            for i in (0..max_glow_index).rev() {
-                source = dest;
-                source_size = rb.get_texture_slice_size(scope, blur0, (i + 3).try_into().unwrap());
-                vp_size = rb.get_texture_slice_size(scope, blur0, (i + 2).try_into().unwrap());
-                dest = rb.get_texture_slice(scope, blur0, 0, (i + 2).try_into().unwrap(), 1, 1);
-                let blend_tex =
-                    rb.get_texture_slice(scope, blur1, 0, (i + 1).try_into().unwrap(), 1, 1);
-                self.upsample.exec(
-                    source,
-                    dest,
-                    blend_tex,
-                    source_size,
-                    vp_size,
-                    glow_levels[TryInto::<usize>::try_into(i).unwrap()],
-                    if i == max_glow_index - 1 {
-                        glow_levels[TryInto::<usize>::try_into(i + 1).unwrap()]
-                    } else {
-                        1.0
+            let mut lens_flare = Rid::Invalid;
+            if use_lens_flare {
+                lens_flare = rb.get_texture_slice(scope, &*RB_TEX_LENS_FLARE, 0, 0, 1, 1);
+                self.lens_flare.exec(
+                    rb.get_texture_slice(scope, blur1, 0, 1, 1, 1),
+                    lens_flare,
+                    rb.get_texture_slice_size(scope, blur1, 1),
+                    LensFlareSettings {
+                        ghost_count: self.lens_flare_ghost_count,
+                        ghost_dispersal: self.lens_flare_ghost_dispersal,
+                        ghost_intensity: self.lens_flare_intensity,
+                        ghost_gradient_tex: lens_flare_ghost_gradient,
+                        halo_width: self.lens_flare_halo_width,
+                        halo_intensity: self.lens_flare_intensity * self.lens_flare_halo_intensity,
+                        chromatic_distortion: self.lens_flare_chromatic_distortion,
+                        starburst_intensity: self.lens_flare_starburst_intensity,
+                        starburst_rotation: lens_flare_starburst_rotation,
                     },
                 );
             }
+            let sharpen_source = if use_cas {
+                rb.get_texture_slice(scope, &*RB_TEX_SHARPEN, 0, 0, 1, 1)
+            } else {
+                color_tex
+            };
+            let tonemap_dest = if smaa_preset.is_some() {
+                rb.get_texture_slice(scope, &*RB_TEX_SMAA_SOURCE, 0, 0, 1, 1)
+            } else {
+                sharpen_source
+            };
             let dest_fb = FramebufferCacheRd::get_cache_multipass(
-                &Array::from(&[color_tex]),
+                &Array::from(&[tonemap_dest]),
                 &Array::new(),
                 1,
             );
             let blur0level0 = rb.get_texture_slice(scope, blur0, 0, 0, 1, 1);
-            let blur0level2 = rb.get_texture_slice(scope, blur0, 0, 2, 1, 1);
+            let lens_dirt_glow = if lens_dirt_glow_level >= 0 {
+                rb.get_texture_slice(
+                    scope,
+                    blur1,
+                    0,
+                    (lens_dirt_glow_level + 1).try_into().unwrap(),
+                    1,
+                    1,
+                )
+            } else {
+                Rid::Invalid
+            };
+            let (glow_tex, glow_tex_size) = if glow_level_count > 0 {
+                (
+                    rb.get_texture_slice(scope, blur0, 0, 2, 1, 1),
+                    rb.get_texture_slice_size(scope, blur0, 2),
+                )
+            } else {
+                (
+                    self.global_rids_singleton.bind().default_texture_black,
+                    Vector2i::ONE,
+                )
+            };
             self.copy.exec(color_tex, blur0level0);
             self.tonemapper.exec(
                 blur0level0,
                 dest_fb,
                 buffer_size,
                 copy::ToneMapSettings {
-                    glow_tex_size: rb.get_texture_slice_size(scope, blur0, 2),
-                    glow_tex: blur0level2,
+                    glow_tex_size,
+                    glow_tex,
                     use_glow_map: glow_map.is_valid(),
                     glow_map_tex: glow_map,
                     glow_intensity,
                     glow_map_strength: self.glow_map_strength,
-                    exposure: self.exposure,
+                    lens_dirt_tex: lens_dirt,
+                    lens_dirt_glow_tex: lens_dirt_glow,
+                    lens_dirt_intensity: self.lens_dirt_intensity,
+                    streak_tex: streak,
+                    lens_flare_tex: lens_flare,
+                    exposure,
                     white: self.white,
-                    use_fxaa: self.use_fxaa,
+                    fxaa: use_fxaa.then(|| FxaaSettings {
+                        quality: self.fxaa_quality,
+                        subpixel_quality: self.fxaa_subpixel_quality,
+                        edge_threshold: self.fxaa_edge_threshold,
+                        edge_threshold_min: self.fxaa_edge_threshold_min,
+                        sharpness: use_fxaa_sharpen.then_some(self.sharpen_intensity),
+                    }),
                     tonemap_type: self.tonemap_type,
+                    tonemap_params: self.tonemap_params(),
+                    tonemap_mode: self.tonemap_mode,
+                    tonemap_mode_blend: self.tonemap_mode_blend,
                     glow_mode: self.glow_blend_mode,
+                    color_lut_tex: self.color_lut_tex,
+                    color_lut_domain_min: self.color_lut_domain_min,
+                    color_lut_domain_max: self.color_lut_domain_max,
+                    custom_curve_tex,
+                    bcs: self.adjustment_enabled.then_some([
+                        self.adjustment_brightness,
+                        self.adjustment_contrast,
+                        self.adjustment_saturation,
+                    ]),
+                    color_correction_tex: color_correction,
+                    color_correction_is_1d,
+                    grading: self.grading_settings(),
+                    vignette: self.vignette_settings(),
+                    grain: self.grain_settings(),
+                    frame: self.frame,
+                    debanding_bit_depth: self
+                        .use_debanding
+                        .then(|| output_bit_depth(color_data_fmt, self.output_mode))
+                        .flatten(),
+                    chromatic_aberration: self.chromatic_aberration_settings(),
+                    lens_distortion: (self.lens_distortion_intensity != 0.0).then_some(
+                        LensDistortionSettings {
+                            intensity: self.lens_distortion_intensity,
+                            crop_compensation: self.lens_distortion_crop_compensation,
+                        },
+                    ),
+                    hdr_output: self.hdr_output_settings(),
                 },
             );
+            if let Some(preset) = smaa_preset {
+                self.smaa.exec(
+                    tonemap_dest,
+                    rb.get_texture_slice(scope, &*RB_TEX_SMAA_EDGES, 0, 0, 1, 1),
+                    rb.get_texture_slice(scope, &*RB_TEX_SMAA_BLEND, 0, 0, 1, 1),
+                    sharpen_source,
+                    buffer_size,
+                    preset,
+                );
+            }
+            if use_cas {
+                self.cas
+                    .exec(sharpen_source, color_tex, self.sharpen_intensity);
+            }
         }
     }
 }
 
+fn create_color_lut_texture(rd: &mut Gd<RenderingDevice>, lut: &CubeLut) -> Rid {
+    let size: u32 = lut.texture_size().try_into().unwrap();
+    let mut format = RdTextureFormat::new_gd();
+    format.set_format(DataFormat::R32G32B32A32_SFLOAT);
+    format.set_texture_type(TextureType::TYPE_3D);
+    format.set_width(size);
+    format.set_height(size);
+    format.set_depth(size);
+    format.set_usage_bits(TextureUsageBits::SAMPLING_BIT);
+    let data = PackedByteArray::from(lut.to_rgba().as_slice().as_bytes());
+    rd.texture_create_ex(&format, &RdTextureView::new_gd())
+        .data(&Array::from(&[data]))
+        .done()
+}
+
+/// Bits per channel the output ends up quantized to, `None` if it isn't dithered. Float
+/// buffers are assumed to be presented on an 8-bit display in SDR and as a 10-bit signal in
+/// HDR10. scRGB stays linear float up to the display, which the sRGB encoded dither doesn't
+/// match.
+fn output_bit_depth(format: DataFormat, mode: OutputMode) -> Option<u32> {
+    if mode == OutputMode::ScRgb {
+        return None;
+    }
+    Some(match format {
+        DataFormat::A2B10G10R10_UNORM_PACK32 | DataFormat::A2R10G10B10_UNORM_PACK32 => 10,
+        DataFormat::R16G16B16A16_UNORM => 16,
+        DataFormat::R16G16B16A16_SFLOAT | DataFormat::R32G32B32A32_SFLOAT
+            if mode == OutputMode::Hdr10 =>
+        {
+            10
+        }
+        _ => 8,
+    })
+}
+
 fn get_image_required_mipmaps(width: u32, height: u32, depth: u32) -> u32 {
     let mut w = width;
     let mut h = height;
@@ -0,0 +1,283 @@
+use godot::{
+    classes::{
+        Engine, RdPipelineSpecializationConstant, RdTextureFormat, RdTextureView, RdUniform,
+        RenderingDevice, RenderingServer, UniformSetCacheRd,
+        rendering_device::{DataFormat, TextureType, TextureUsageBits, UniformType},
+    },
+    prelude::*,
+};
+use zerocopy::FromBytes;
+
+use crate::{
+    GlobalRidsSingleton,
+    post_effect::{
+        copy::Raster,
+        smaa_tables::{AREATEX_SIZE, SEARCHTEX_HEIGHT, SEARCHTEX_WIDTH, area_tex, search_tex},
+    },
+};
+
+const SMAA_EDGE_DETECTION_SHADER_PATH: &str = "uid://b2cbro7511ff5";
+const SMAA_BLENDING_WEIGHTS_SHADER_PATH: &str = "uid://bc8sbdydbgrot";
+const SMAA_NEIGHBORHOOD_BLENDING_SHADER_PATH: &str = "uid://b9wtnklvkr85s";
+
+/// Anti-aliasing of the tonemapped image.
+///
+/// SMAA only detects orthogonal edges. The presets use the threshold, search steps and corner
+/// rounding of the reference presets, but High and Ultra lack their diagonal detection.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum AntiAliasing {
+    None,
+    /// FXAA inside the tonemap pass.
+    Fxaa,
+    SmaaLow,
+    SmaaMedium,
+    /// Reference High without diagonal detection.
+    SmaaHigh,
+    /// Reference Ultra without diagonal detection.
+    SmaaUltra,
+}
+
+/// Quality settings of the SMAA presets, orthogonal edges only.
+#[derive(Clone, Copy)]
+pub struct SmaaPreset {
+    threshold: f32,
+    max_search_steps: u32,
+    /// Percent, `None` disables corner detection.
+    corner_rounding: Option<u32>,
+}
+
+impl AntiAliasing {
+    pub fn smaa_preset(self) -> Option<SmaaPreset> {
+        let (threshold, max_search_steps, corner_rounding) = match self {
+            Self::None | Self::Fxaa => return None,
+            Self::SmaaLow => (0.15, 4, None),
+            Self::SmaaMedium => (0.1, 8, None),
+            Self::SmaaHigh => (0.1, 16, Some(25)),
+            Self::SmaaUltra => (0.05, 32, Some(25)),
+        };
+        Some(SmaaPreset {
+            threshold,
+            max_search_steps,
+            corner_rounding,
+        })
+    }
+}
+
+#[derive(
+    Debug,
+    zerocopy::FromBytes,
+    zerocopy::IntoBytes,
+    zerocopy::Immutable,
+    zerocopy::KnownLayout,
+    Default,
+)]
+#[repr(C)]
+struct SmaaPushConstants {
+    rt_metrics: [f32; 4],      // 16 - 16
+    threshold: f32,            // 04 - 20
+    max_search_steps: f32,     // 04 - 24
+    corner_rounding_norm: f32, // 04 - 28
+    pad: f32,                  // 04 - 32
+}
+
+/// SMAA 1x without diagonal detection: edge detection, blending weight calculation and
+/// neighborhood blending.
+pub struct Smaa {
+    edge_detection: Raster,
+    blending_weights: Raster,
+    neighborhood_blending: Raster,
+    scs: Array<Gd<RdPipelineSpecializationConstant>>,
+    ubo: PackedArray<u8>,
+    uniforms_edge_detection: Array<Gd<RdUniform>>,
+    uniforms_blending_weights: Array<Gd<RdUniform>>,
+    uniforms_neighborhood_blending: Array<Gd<RdUniform>>,
+    sampler: Rid,
+    nearest_sampler: Rid,
+    area_tex: Rid,
+    search_tex: Rid,
+}
+
+impl Drop for Smaa {
+    fn drop(&mut self) {
+        let mut rd = RenderingServer::singleton().get_rendering_device().unwrap();
+        if self.area_tex.is_valid() {
+            rd.free_rid(self.area_tex);
+        }
+        if self.search_tex.is_valid() {
+            rd.free_rid(self.search_tex);
+        }
+    }
+}
+
+fn sampler_uniforms(count: i32) -> Array<Gd<RdUniform>> {
+    let mut uniforms = Array::new();
+    for binding in 0..count {
+        let mut uniform = RdUniform::new_gd();
+        uniform.set_uniform_type(UniformType::SAMPLER_WITH_TEXTURE);
+        uniform.set_binding(binding);
+        uniforms.push(&uniform);
+    }
+    uniforms
+}
+
+fn create_lookup_texture(
+    rd: &mut Gd<RenderingDevice>,
+    format: DataFormat,
+    width: usize,
+    height: usize,
+    data: &[u8],
+) -> Rid {
+    let mut tex_format = RdTextureFormat::new_gd();
+    tex_format.set_format(format);
+    tex_format.set_texture_type(TextureType::TYPE_2D);
+    tex_format.set_width(width.try_into().unwrap());
+    tex_format.set_height(height.try_into().unwrap());
+    tex_format.set_usage_bits(TextureUsageBits::SAMPLING_BIT);
+    rd.texture_create_ex(&tex_format, &RdTextureView::new_gd())
+        .data(&Array::from(&[PackedByteArray::from(data)]))
+        .done()
+}
+
+impl Smaa {
+    pub fn init() -> Self {
+        let ubo_bytes: [u8; std::mem::size_of::<SmaaPushConstants>()] =
+            zerocopy::transmute!(SmaaPushConstants::default());
+        let ubo = PackedArray::<u8>::from(&ubo_bytes);
+
+        let (sampler, nearest_sampler) = {
+            let singleton = Engine::singleton()
+                .get_singleton(&GlobalRidsSingleton::class_name().to_string_name())
+                .unwrap()
+                .cast::<GlobalRidsSingleton>();
+            let singleton = singleton.bind();
+            (singleton.default_sampler, singleton.nearest_sampler)
+        };
+
+        let mut rd = RenderingServer::singleton().get_rendering_device().unwrap();
+        let area_tex = create_lookup_texture(
+            &mut rd,
+            DataFormat::R8G8_UNORM,
+            AREATEX_SIZE,
+            AREATEX_SIZE,
+            &area_tex(),
+        );
+        let search_tex = create_lookup_texture(
+            &mut rd,
+            DataFormat::R8_UNORM,
+            SEARCHTEX_WIDTH,
+            SEARCHTEX_HEIGHT,
+            &search_tex(),
+        );
+
+        Self {
+            edge_detection: Raster::load_shader_file_path(SMAA_EDGE_DETECTION_SHADER_PATH),
+            blending_weights: Raster::load_shader_file_path(SMAA_BLENDING_WEIGHTS_SHADER_PATH),
+            neighborhood_blending: Raster::load_shader_file_path(
+                SMAA_NEIGHBORHOOD_BLENDING_SHADER_PATH,
+            ),
+            scs: Array::new(),
+            ubo,
+            uniforms_edge_detection: sampler_uniforms(1),
+            uniforms_blending_weights: sampler_uniforms(3),
+            uniforms_neighborhood_blending: sampler_uniforms(2),
+            sampler,
+            nearest_sampler,
+            area_tex,
+            search_tex,
+        }
+    }
+
+    /// Anti-aliases `source_rd_texture` into `dest_texture`. `edges_texture` (RG8) and
+    /// `blend_texture` (RGBA8) are intermediates of the same size.
+    pub fn exec(
+        &mut self,
+        source_rd_texture: Rid,
+        edges_texture: Rid,
+        blend_texture: Rid,
+        dest_texture: Rid,
+        size: Vector2i,
+        preset: SmaaPreset,
+    ) {
+        // UBO, shared by the three passes.
+        let ubo = self.ubo.as_mut_slice();
+        let ubo_mut = SmaaPushConstants::mut_from_bytes(ubo).unwrap();
+        let (width, height) = (size.x as f32, size.y as f32);
+        ubo_mut.rt_metrics = [1.0 / width, 1.0 / height, width, height];
+        ubo_mut.threshold = preset.threshold;
+        ubo_mut.max_search_steps = preset.max_search_steps as f32;
+        ubo_mut.corner_rounding_norm = preset
+            .corner_rounding
+            .map_or(1.0, |rounding| rounding as f32 / 100.0);
+
+        // Edge detection.
+        self.edge_detection
+            .setup_pipeline_texure(edges_texture, &self.scs);
+        let mut uniform_src_tex = self.uniforms_edge_detection.get(0).unwrap();
+        uniform_src_tex.clear_ids();
+        uniform_src_tex.add_id(self.sampler);
+        uniform_src_tex.add_id(source_rd_texture);
+        let uniform_set0 = UniformSetCacheRd::get_cache(
+            self.edge_detection.shader,
+            0,
+            &self.uniforms_edge_detection,
+        );
+        Self::draw(&mut self.edge_detection, &self.ubo, uniform_set0);
+
+        // Blending weight calculation.
+        self.blending_weights
+            .setup_pipeline_texure(blend_texture, &self.scs);
+        let textures = [
+            (self.sampler, edges_texture),
+            (self.sampler, self.area_tex),
+            (self.nearest_sampler, self.search_tex),
+        ];
+        for (i, (sampler, texture)) in textures.into_iter().enumerate() {
+            let mut uniform = self.uniforms_blending_weights.get(i).unwrap();
+            uniform.clear_ids();
+            uniform.add_id(sampler);
+            uniform.add_id(texture);
+        }
+        let uniform_set0 = UniformSetCacheRd::get_cache(
+            self.blending_weights.shader,
+            0,
+            &self.uniforms_blending_weights,
+        );
+        Self::draw(&mut self.blending_weights, &self.ubo, uniform_set0);
+
+        // Neighborhood blending.
+        self.neighborhood_blending
+            .setup_pipeline_texure(dest_texture, &self.scs);
+        for (i, texture) in [source_rd_texture, blend_texture].into_iter().enumerate() {
+            let mut uniform = self.uniforms_neighborhood_blending.get(i).unwrap();
+            uniform.clear_ids();
+            uniform.add_id(self.sampler);
+            uniform.add_id(texture);
+        }
+        let uniform_set0 = UniformSetCacheRd::get_cache(
+            self.neighborhood_blending.shader,
+            0,
+            &self.uniforms_neighborhood_blending,
+        );
+        Self::draw(&mut self.neighborhood_blending, &self.ubo, uniform_set0);
+    }
+
+    fn draw(renderer: &mut Raster, ubo: &PackedArray<u8>, uniform_set0: Rid) {
+        let draw_list = renderer.rd.draw_list_begin(renderer.framebuffer);
+        renderer
+            .rd
+            .draw_list_bind_render_pipeline(draw_list, renderer.pipeline);
+        renderer
+            .rd
+            .draw_list_set_push_constant(draw_list, ubo, ubo.len().try_into().unwrap());
+        renderer
+            .rd
+            .draw_list_bind_uniform_set(draw_list, uniform_set0, 0);
+        renderer
+            .rd
+            .draw_list_draw_ex(draw_list, false, 1)
+            .procedural_vertex_count(3)
+            .done();
+        renderer.rd.draw_list_end();
+    }
+}
@@ -0,0 +1,325 @@
+//! Lookup textures of SMAA 1x, ported from `AreaTex.py` and `SearchTex.py` of
+//! <https://github.com/iryoku/smaa>.
+//!
+//! Only the orthogonal areas without subsample offset are generated, which is all SMAA 1x
+//! without diagonal detection samples.
+
+/// Quadratically compressed distance covered by one pattern in the area texture.
+pub const AREATEX_MAX_DISTANCE: usize = 16;
+/// The patterns are laid out on a 5x5 grid, indexed by the rounded crossing edge values.
+pub const AREATEX_SIZE: usize = 5 * AREATEX_MAX_DISTANCE;
+pub const SEARCHTEX_WIDTH: usize = 64;
+pub const SEARCHTEX_HEIGHT: usize = 16;
+
+const SMOOTH_MAX_DISTANCE: f64 = 32.0;
+
+/// Where each orthogonal pattern is placed in the area texture.
+const EDGES_ORTHO: [(usize, usize); 16] = [
+    (0, 0),
+    (3, 0),
+    (0, 3),
+    (3, 3),
+    (1, 0),
+    (4, 0),
+    (1, 3),
+    (4, 3),
+    (0, 1),
+    (3, 1),
+    (0, 4),
+    (3, 4),
+    (1, 1),
+    (4, 1),
+    (1, 4),
+    (4, 4),
+];
+
+type Area = [f64; 2];
+
+fn add(a: Area, b: Area) -> Area {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn lerp(a: Area, b: Area, p: f64) -> Area {
+    [a[0] + (b[0] - a[0]) * p, a[1] + (b[1] - a[1]) * p]
+}
+
+/// Area under the line `p1` -> `p2` for the pixel `x..x + 1`, split into the parts below
+/// and above the edge.
+fn area(p1: (f64, f64), p2: (f64, f64), x: f64) -> Area {
+    let d = (p2.0 - p1.0, p2.1 - p1.1);
+    let x1 = x;
+    let x2 = x + 1.0;
+    let y1 = p1.1 + d.1 * (x1 - p1.0) / d.0;
+    let y2 = p1.1 + d.1 * (x2 - p1.0) / d.0;
+
+    let inside = (x1 >= p1.0 && x1 < p2.0) || (x2 > p1.0 && x2 <= p2.0);
+    if !inside {
+        return [0.0, 0.0];
+    }
+    let is_trapezoid = y1.signum() == y2.signum() || y1.abs() < 1e-4 || y2.abs() < 1e-4;
+    if is_trapezoid {
+        let a = (y1 + y2) / 2.0;
+        if a < 0.0 {
+            [a.abs(), 0.0]
+        } else {
+            [0.0, a.abs()]
+        }
+    } else {
+        // Two triangles.
+        let x = -p1.1 * d.0 / d.1 + p1.0;
+        let a1 = if x > p1.0 { y1 * x.fract() / 2.0 } else { 0.0 };
+        let a2 = if x < p2.0 {
+            y2 * (1.0 - x.fract()) / 2.0
+        } else {
+            0.0
+        };
+        let a = if a1.abs() > a2.abs() { a1 } else { -a2 };
+        if a < 0.0 {
+            [a1.abs(), a2.abs()]
+        } else {
+            [a2.abs(), a1.abs()]
+        }
+    }
+}
+
+/// Smoothing for short U patterns.
+fn smooth_area(d: f64, a1: Area, a2: Area) -> (Area, Area) {
+    let b1 = a1.map(|a| (2.0 * a).sqrt() * 0.5);
+    let b2 = a2.map(|a| (2.0 * a).sqrt() * 0.5);
+    let p = (d / SMOOTH_MAX_DISTANCE).clamp(0.0, 1.0);
+    (lerp(b1, a1, p), lerp(b2, a2, p))
+}
+
+/// Coverage of the pixel `left` pixels away from the left end of an edge of `pattern`.
+fn area_ortho(pattern: usize, left: f64, right: f64, offset: f64) -> Area {
+    let d = left + right + 1.0;
+    let o1 = 0.5 + offset;
+    let o2 = 0.5 + offset - 1.0;
+    let half = d / 2.0;
+
+    match pattern {
+        // L patterns are only revectorized on the crossing edge side, so that they
+        // converge with the unfiltered pattern 0.
+        1 if left <= right => area((0.0, o2), (half, 0.0), left),
+        2 if left >= right => area((half, 0.0), (d, o2), left),
+        3 => {
+            let a1 = area((0.0, o2), (half, 0.0), left);
+            let a2 = area((half, 0.0), (d, o2), left);
+            let (a1, a2) = smooth_area(d, a1, a2);
+            add(a1, a2)
+        }
+        4 if left <= right => area((0.0, o1), (half, 0.0), left),
+        // Blend full Z patterns with L patterns to avoid discontinuities at the search range.
+        6 if offset.abs() > 0.0 => {
+            let a1 = area((0.0, o1), (d, o2), left);
+            let a2 = add(
+                area((0.0, o1), (half, 0.0), left),
+                area((half, 0.0), (d, o2), left),
+            );
+            add(a1, a2).map(|a| a / 2.0)
+        }
+        6 | 14 => area((0.0, o1), (d, o2), left),
+        7 => area((0.0, o1), (d, o2), left),
+        8 if left >= right => area((half, 0.0), (d, o1), left),
+        9 if offset.abs() > 0.0 => {
+            let a1 = area((0.0, o2), (d, o1), left);
+            let a2 = add(
+                area((0.0, o2), (half, 0.0), left),
+                area((half, 0.0), (d, o1), left),
+            );
+            add(a1, a2).map(|a| a / 2.0)
+        }
+        9 | 11 | 13 => area((0.0, o2), (d, o1), left),
+        12 => {
+            let a1 = area((0.0, o1), (half, 0.0), left);
+            let a2 = area((half, 0.0), (d, o1), left);
+            let (a1, a2) = smooth_area(d, a1, a2);
+            add(a1, a2)
+        }
+        // No edge, or crossing edges on both sides of an end.
+        _ => [0.0, 0.0],
+    }
+}
+
+/// `R8G8_UNORM` area texture of `AREATEX_SIZE`², rows from the top.
+pub fn area_tex() -> Vec<u8> {
+    let mut tex = vec![0u8; AREATEX_SIZE * AREATEX_SIZE * 2];
+    for (pattern, (tile_x, tile_y)) in EDGES_ORTHO.iter().enumerate() {
+        for y in 0..AREATEX_MAX_DISTANCE {
+            for x in 0..AREATEX_MAX_DISTANCE {
+                // Distances are stored square rooted.
+                let left = (x * x) as f64;
+                let right = (y * y) as f64;
+                let a = area_ortho(pattern, left, right, 0.0);
+                let px = tile_x * AREATEX_MAX_DISTANCE + x;
+                let py = tile_y * AREATEX_MAX_DISTANCE + y;
+                let i = (py * AREATEX_SIZE + px) * 2;
+                tex[i] = (255.0 * a[0]).round() as u8;
+                tex[i + 1] = (255.0 * a[1]).round() as u8;
+            }
+        }
+    }
+    tex
+}
+
+/// Value of a bilinear fetch between 4 edges at (-0.25, -0.125), in 1/32 units:
+///
+/// ```text
+/// e[0]   e[1]
+///      x
+/// e[2]   e[3] <- current pixel
+/// ```
+fn bilinear(e: [u8; 4]) -> usize {
+    // lerp(lerp(e0, e1, 0.75), lerp(e2, e3, 0.75), 0.875) * 32.
+    usize::from(e[0]) + 3 * usize::from(e[1]) + 7 * usize::from(e[2]) + 21 * usize::from(e[3])
+}
+
+/// Reverse lookup of `bilinear`.
+fn edges(value: usize) -> Option<[u8; 4]> {
+    (0..16u8)
+        .map(|i| [i & 1, (i >> 1) & 1, (i >> 2) & 1, (i >> 3) & 1])
+        .find(|&e| bilinear(e) == value)
+}
+
+fn delta_left(left: [u8; 4], top: [u8; 4]) -> u8 {
+    let mut d = 0;
+    // If there is an edge, continue.
+    if top[3] == 1 {
+        d += 1;
+    }
+    // If we previously found an edge, there is another edge and no crossing edges, continue.
+    if d == 1 && top[2] == 1 && left[1] != 1 && left[3] != 1 {
+        d += 1;
+    }
+    d
+}
+
+fn delta_right(left: [u8; 4], top: [u8; 4]) -> u8 {
+    let mut d = 0;
+    // If there is an edge, and no crossing edges, continue.
+    if top[3] == 1 && left[1] != 1 && left[3] != 1 {
+        d += 1;
+    }
+    // If we previously found an edge, there is another edge and no crossing edges, continue.
+    if d == 1 && top[2] == 1 && left[0] != 1 && left[2] != 1 {
+        d += 1;
+    }
+    d
+}
+
+type Delta = fn([u8; 4], [u8; 4]) -> u8;
+
+/// `R8_UNORM` search texture of `SEARCHTEX_WIDTH` x `SEARCHTEX_HEIGHT`, rows from the top.
+///
+/// The full table is 66x33 with the left searches in the first 33 columns. Like the
+/// reference it's cropped to the rows that can be non-zero and flipped vertically.
+pub fn search_tex() -> Vec<u8> {
+    const FULL_HEIGHT: usize = 33;
+    let mut tex = vec![0u8; SEARCHTEX_WIDTH * SEARCHTEX_HEIGHT];
+    for (row, texel_row) in tex.chunks_exact_mut(SEARCHTEX_WIDTH).enumerate() {
+        let y = FULL_HEIGHT - 1 - row;
+        for (column, texel) in texel_row.iter_mut().enumerate() {
+            let (x, delta): (usize, Delta) = if column < FULL_HEIGHT {
+                (column, delta_left)
+            } else {
+                (column - FULL_HEIGHT, delta_right)
+            };
+            if let (Some(left), Some(top)) = (edges(x), edges(y)) {
+                // Maximizes the dynamic range like the reference.
+                *texel = 127 * delta(left, top);
+            }
+        }
+    }
+    tex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area_at(tex: &[u8], x: usize, y: usize) -> [u8; 2] {
+        let i = (y * AREATEX_SIZE + x) * 2;
+        [tex[i], tex[i + 1]]
+    }
+
+    #[test]
+    fn bilinear_values_are_unique() {
+        for value in 0..=32 {
+            let count = (0..16u8)
+                .map(|i| [i & 1, (i >> 1) & 1, (i >> 2) & 1, (i >> 3) & 1])
+                .filter(|&e| bilinear(e) == value)
+                .count();
+            assert!(count <= 1, "{value}");
+        }
+        assert_eq!(edges(21), Some([0, 0, 0, 1]));
+        assert_eq!(edges(2), None);
+    }
+
+    #[test]
+    fn search_tex_deltas() {
+        let tex = search_tex();
+        assert_eq!(tex.len(), SEARCHTEX_WIDTH * SEARCHTEX_HEIGHT);
+        assert!(tex.iter().all(|&v| v == 0 || v == 127 || v == 254));
+        // No crossing edges, an edge on top: continue one step.
+        assert_eq!(tex[(32 - 21) * SEARCHTEX_WIDTH], 127);
+        // Two edges on top: continue two steps.
+        assert_eq!(tex[(32 - 28) * SEARCHTEX_WIDTH], 254);
+        // A crossing edge stops the right search after the first step.
+        let left = bilinear([0, 1, 0, 0]);
+        assert_eq!(tex[(32 - 28) * SEARCHTEX_WIDTH + 33 + left], 0);
+    }
+
+    #[test]
+    fn area_of_l_pattern() {
+        // Pattern 1 with both distances 0: a triangle covering 1/8 of the pixel below.
+        assert_eq!(area_ortho(1, 0.0, 0.0, 0.0), [0.125, 0.0]);
+        let tex = area_tex();
+        let (tile_x, tile_y) = EDGES_ORTHO[1];
+        let texel = area_at(
+            &tex,
+            tile_x * AREATEX_MAX_DISTANCE,
+            tile_y * AREATEX_MAX_DISTANCE,
+        );
+        assert_eq!(texel, [32, 0]);
+    }
+
+    #[test]
+    fn areas_without_revectorization() {
+        let tex = area_tex();
+        for pattern in [0, 5, 10, 15] {
+            let (tile_x, tile_y) = EDGES_ORTHO[pattern];
+            for y in 0..AREATEX_MAX_DISTANCE {
+                for x in 0..AREATEX_MAX_DISTANCE {
+                    let texel = area_at(
+                        &tex,
+                        tile_x * AREATEX_MAX_DISTANCE + x,
+                        tile_y * AREATEX_MAX_DISTANCE + y,
+                    );
+                    assert_eq!(texel, [0, 0], "pattern {pattern}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn areas_are_bounded() {
+        for pattern in 0..16 {
+            for left in 0..AREATEX_MAX_DISTANCE {
+                for right in 0..AREATEX_MAX_DISTANCE {
+                    let (l, r) = ((left * left) as f64, (right * right) as f64);
+                    let a = area_ortho(pattern, l, r, 0.0);
+                    assert!(a.iter().all(|v| (0.0..=1.0).contains(v)), "{pattern} {a:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn z_pattern_is_symmetric() {
+        // Pattern 6 (top left to bottom right) covers the pixels at both ends equally.
+        let first = area_ortho(6, 0.0, 3.0, 0.0);
+        let last = area_ortho(6, 3.0, 0.0, 0.0);
+        assert!((first[0] - last[1]).abs() < 1e-9, "{first:?} {last:?}");
+        assert!((first[1] - last[0]).abs() < 1e-9, "{first:?} {last:?}");
+    }
+}
@@ -0,0 +1,3 @@
+pub fn assert_close(a: f32, b: f32, eps: f32) {
+    assert!((a - b).abs() < eps, "{a} != {b}");
+}